@@ -0,0 +1,51 @@
+use soyuz_app::prelude::*;
+
+fn main() {
+    // The screenshot goes to the path given as the first argument, or the temp directory.
+    let path = std::env::args_os()
+        .nth(1)
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("headless.png"));
+
+    let mut ctx = GraphicsBuilder::new()
+        .force_fallback_adapter(true)
+        .build_headless(256, 256)
+        .expect("Failed to create headless context");
+
+    let shader = ctx.shader(include_str!("../triangle/triangle.wgsl"));
+
+    let pipeline = ctx
+        .render_pipeline()
+        .shader(shader.module())
+        .label("Headless Triangle Pipeline")
+        .build();
+
+    for frame in 0..4 {
+        if frame == 3 {
+            ctx.save_screenshot(&path);
+        }
+
+        ctx.render(|ctx, view, encoder| {
+            let mut render_pass = ctx
+                .render_pass(encoder, view)
+                .clear_rgb(0.1, 0.1, 0.1)
+                .label("Headless Render Pass")
+                .begin();
+
+            render_pass.set_pipeline(&pipeline);
+            render_pass.draw(0..3, 0..1);
+        });
+    }
+
+    ctx.device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .expect("Failed to wait for the device");
+
+    println!(
+        "Rendered 4 frames into a {}x{} {:?} offscreen target, last frame saved to {}",
+        ctx.width(),
+        ctx.height(),
+        ctx.config.format,
+        path.display()
+    );
+}
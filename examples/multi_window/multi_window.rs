@@ -0,0 +1,66 @@
+use soyuz_app::prelude::*;
+
+struct MultiWindowApp {
+    main_window: Option<winit::window::WindowId>,
+    opened: u32,
+}
+
+impl App for MultiWindowApp {
+    fn init(ctx: &mut Context) -> Self {
+        println!("Controls:");
+        println!("  N - Open a new window");
+        println!("  W - Close the focused secondary window");
+
+        Self {
+            main_window: ctx.window_id(),
+            opened: 0,
+        }
+    }
+
+    fn frame(&mut self, ctx: &mut Context, _dt: f32) {
+        let (r, g, b) = if ctx.window_id() == self.main_window {
+            (0.1, 0.2, 0.3)
+        } else {
+            (0.3, 0.2, 0.1)
+        };
+
+        ctx.render(|ctx, view, encoder| {
+            let _pass = ctx
+                .render_pass(encoder, view)
+                .clear_rgb(r, g, b)
+                .label("Multi Window Pass")
+                .begin();
+        });
+    }
+
+    fn key_pressed(&mut self, ctx: &mut Context, key: KeyCode) {
+        match key {
+            KeyCode::KeyN => {
+                self.opened += 1;
+                ctx.open_window(
+                    winit::window::Window::default_attributes()
+                        .with_title(format!("Window {}", self.opened))
+                        .with_inner_size(winit::dpi::LogicalSize::new(400, 300)),
+                );
+            }
+            KeyCode::KeyW if ctx.window_id() != self.main_window => {
+                if let Some(id) = ctx.window_id() {
+                    ctx.close_window(id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn window_opened(&mut self, ctx: &mut Context) {
+        println!("Opened window {:?}", ctx.window_id());
+    }
+
+    fn window_closed(&mut self, ctx: &mut Context) {
+        println!("Closed window {:?}", ctx.window_id());
+    }
+}
+
+fn main() {
+    soyuz_app::run::<MultiWindowApp>("Multi Window Example");
+}
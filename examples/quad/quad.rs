@@ -0,0 +1,93 @@
+use bytemuck::{Pod, Zeroable};
+use soyuz_app::prelude::*;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 2],
+    color: [f32; 3],
+}
+
+const VERTICES: &[Vertex] = &[
+    Vertex {
+        position: [-0.5, 0.5],
+        color: [1.0, 0.0, 0.0],
+    },
+    Vertex {
+        position: [-0.5, -0.5],
+        color: [0.0, 1.0, 0.0],
+    },
+    Vertex {
+        position: [0.5, -0.5],
+        color: [0.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, 0.5],
+        color: [1.0, 1.0, 0.0],
+    },
+];
+
+const INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Params {
+    angle: f32,
+}
+
+struct QuadApp {
+    pipeline: wgpu::RenderPipeline,
+    vertices: Buffer<Vertex>,
+    indices: Buffer<u16>,
+    params: Uniform<Params>,
+    angle: f32,
+}
+
+impl App for QuadApp {
+    fn init(ctx: &mut Context) -> Self {
+        let shader = ctx.shader(include_str!("quad.wgsl"));
+        let params = ctx.uniform(&Params { angle: 0.0 }, 0, wgpu::ShaderStages::VERTEX);
+
+        let pipeline = ctx
+            .render_pipeline()
+            .shader_from(&shader)
+            .vertex_buffer(wgpu::VertexBufferLayout {
+                array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3],
+            })
+            .uniform(&params)
+            .label("Quad Pipeline")
+            .build();
+
+        Self {
+            pipeline,
+            vertices: ctx.vertex_buffer(VERTICES),
+            indices: ctx.index_buffer(INDICES),
+            params,
+            angle: 0.0,
+        }
+    }
+
+    fn frame(&mut self, ctx: &mut Context, dt: f32) {
+        self.angle += dt;
+        self.params.set(&ctx.queue, &Params { angle: self.angle });
+
+        ctx.render(|ctx, view, encoder| {
+            let mut render_pass = ctx
+                .render_pass(encoder, view)
+                .clear_rgb(0.1, 0.1, 0.1)
+                .label("Quad Render Pass")
+                .begin();
+
+            render_pass.set_pipeline(&self.pipeline);
+            self.params.bind(&mut render_pass, 0);
+            render_pass.set_typed_vertex_buffer(0, &self.vertices);
+            render_pass.draw_indexed_buffer(&self.indices, 0..1);
+        });
+    }
+}
+
+fn main() {
+    soyuz_app::run::<QuadApp>("Quad Example");
+}
@@ -0,0 +1,35 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+struct Params {
+    angle: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    let c = cos(params.angle);
+    let s = sin(params.angle);
+    let position = mat2x2<f32>(c, s, -s, c) * in.position;
+
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.color = in.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -5,7 +5,7 @@ struct SettingsDemo {
 }
 
 impl App for SettingsDemo {
-    fn init(_ctx: &mut Context) -> Self {
+    fn init(ctx: &mut Context) -> Self {
         println!("Controls:");
         println!("  F11 or F - Toggle fullscreen");
         println!("  V - Toggle VSync");
@@ -14,6 +14,10 @@ impl App for SettingsDemo {
         println!("  3 - Set window to 1920x1080");
         println!("  M - Toggle maximize");
         println!("  ESC - Exit fullscreen");
+        println!(
+            "Supported present modes: {:?}",
+            ctx.supported_present_modes()
+        );
 
         Self {
             vsync_enabled: false, // Par défaut Immediate mode
@@ -46,45 +50,35 @@ impl App for SettingsDemo {
                 } else {
                     wgpu::PresentMode::Immediate
                 };
-                ctx.set_present_mode(mode);
+                let mode = ctx.set_present_mode(mode);
                 println!("VSync: {} (mode: {:?})", self.vsync_enabled, mode);
             }
 
             // Preset resolutions
-            KeyCode::Digit1 => {
-                if !ctx.is_fullscreen() {
-                    ctx.set_window_size(800, 600);
-                    println!("Window size set to 800x600");
-                }
+            KeyCode::Digit1 if !ctx.is_fullscreen() => {
+                ctx.set_window_size(800, 600);
+                println!("Window size set to 800x600");
             }
-            KeyCode::Digit2 => {
-                if !ctx.is_fullscreen() {
-                    ctx.set_window_size(1280, 720);
-                    println!("Window size set to 1280x720");
-                }
+            KeyCode::Digit2 if !ctx.is_fullscreen() => {
+                ctx.set_window_size(1280, 720);
+                println!("Window size set to 1280x720");
             }
-            KeyCode::Digit3 => {
-                if !ctx.is_fullscreen() {
-                    ctx.set_window_size(1920, 1080);
-                    println!("Window size set to 1920x1080");
-                }
+            KeyCode::Digit3 if !ctx.is_fullscreen() => {
+                ctx.set_window_size(1920, 1080);
+                println!("Window size set to 1920x1080");
             }
 
             // Toggle maximize
-            KeyCode::KeyM => {
-                if !ctx.is_fullscreen() {
-                    let maximized = !ctx.is_maximized();
-                    ctx.set_maximized(maximized);
-                    println!("Maximized: {}", maximized);
-                }
+            KeyCode::KeyM if !ctx.is_fullscreen() => {
+                let maximized = !ctx.is_maximized();
+                ctx.set_maximized(maximized);
+                println!("Maximized: {}", maximized);
             }
 
             // Exit fullscreen with ESC
-            KeyCode::Escape => {
-                if ctx.is_fullscreen() {
-                    ctx.set_fullscreen(false);
-                    println!("Exited fullscreen");
-                }
+            KeyCode::Escape if ctx.is_fullscreen() => {
+                ctx.set_fullscreen(false);
+                println!("Exited fullscreen");
             }
 
             _ => {}
@@ -99,7 +93,8 @@ impl App for SettingsDemo {
 
 fn main() {
     builder()
-        .title("Settings Demo - Press F11 for fullscreen, V for VSync")
+        .title("Settings Demo - Press F11 for fullscreen, V for VSync, F12 for a screenshot")
         .size(1280, 720)
+        .screenshot_key(KeyCode::F12)
         .run::<SettingsDemo>();
 }
@@ -1,11 +1,17 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::KeyCode;
 use winit::window::{Window, WindowId};
 
-use soyuz_gfx::{Context, GraphicsBuilder};
+use soyuz_gfx::{Context, GraphicsBuilder, WindowRequest};
+
+use crate::Error;
+use crate::record::Recorder;
 
 pub trait App: 'static + Sized {
     /// Initializes the application with the graphics context.
@@ -27,7 +33,8 @@ pub trait App: 'static + Sized {
     /// Called every frame to update and render the application.
     ///
     /// This function is called continuously on every render frame. It is responsible
-    /// for updating the application logic and rendering the current frame.
+    /// for updating the application logic and rendering the current frame. When several
+    /// windows are open, it is called for each of them with that window's [`Context`].
     ///
     /// # Arguments
     ///
@@ -261,6 +268,38 @@ pub trait App: 'static + Sized {
     /// * `ctx` - A mutable reference to the graphics [`Context`].
     fn resumed(&mut self, _ctx: &mut Context) {}
 
+    /// Called after the graphics device was lost and the context recreated.
+    ///
+    /// Every resource created from the previous device (buffers, textures, pipelines, bind
+    /// groups, etc.) is invalid at this point. Override this function to rebuild them.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - A mutable reference to the recreated graphics [`Context`].
+    fn device_recreated(&mut self, _ctx: &mut Context) {}
+
+    /// Called when a window requested with [`Context::open_window`] has been created.
+    ///
+    /// Events for that window are then delivered to the other hooks with its own
+    /// [`Context`], which shares the device and queue of the main window. Use
+    /// [`Context::window_id`] to tell windows apart.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - A mutable reference to the graphics [`Context`] of the new window.
+    fn window_opened(&mut self, _ctx: &mut Context) {}
+
+    /// Called when a secondary window is about to close.
+    ///
+    /// This function is called when a window opened with [`Context::open_window`] is closed
+    /// by the user or with [`Context::close_window`]. Closing the main window calls
+    /// [`App::cleanup`] instead and exits the application.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - A mutable reference to the graphics [`Context`] of the closing window.
+    fn window_closed(&mut self, _ctx: &mut Context) {}
+
     /// Called when the application is about to close.
     ///
     /// This function is called when the window is closed or the application is shutting down.
@@ -281,6 +320,13 @@ pub struct AppBuilder {
     resizable: bool,
     fullscreen: bool,
     maximized: bool,
+    transparent: bool,
+    decorations: bool,
+    screenshot_key: Option<KeyCode>,
+    screenshot_dir: PathBuf,
+    record_dir: Option<PathBuf>,
+    record_fps: u32,
+    record_y4m: bool,
     graphics: GraphicsBuilder,
 }
 
@@ -294,6 +340,13 @@ impl Default for AppBuilder {
             resizable: true,
             fullscreen: false,
             maximized: false,
+            transparent: false,
+            decorations: true,
+            screenshot_key: None,
+            screenshot_dir: PathBuf::from("screenshots"),
+            record_dir: None,
+            record_fps: 60,
+            record_y4m: false,
             graphics: GraphicsBuilder::default(),
         }
     }
@@ -335,6 +388,65 @@ impl AppBuilder {
         self
     }
 
+    /// Makes the window background transparent so that pixels cleared or drawn with an
+    /// alpha below 1 show what is behind the window.
+    ///
+    /// This also sets the graphics alpha mode preference to `PreMultiplied`, then
+    /// `PostMultiplied`; call [`GraphicsBuilder::alpha_modes`] afterwards to override it.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        if transparent {
+            self.graphics = std::mem::take(&mut self.graphics).alpha_modes(&[
+                wgpu::CompositeAlphaMode::PreMultiplied,
+                wgpu::CompositeAlphaMode::PostMultiplied,
+            ]);
+        }
+        self
+    }
+
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Saves a screenshot of the window as a PNG file whenever `key` is pressed.
+    ///
+    /// Screenshots are written to the directory set with [`AppBuilder::screenshot_dir`].
+    /// The key press is still delivered to [`App::key_pressed`].
+    pub fn screenshot_key(mut self, key: KeyCode) -> Self {
+        self.screenshot_key = Some(key);
+        self
+    }
+
+    /// Sets the directory screenshots are saved to. Defaults to `screenshots`.
+    pub fn screenshot_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.screenshot_dir = dir.into();
+        self
+    }
+
+    /// Records every frame of the main window to `dir` as a numbered PNG sequence.
+    ///
+    /// While recording, [`App::frame`] receives a fixed `dt` of `1 / fps` (see
+    /// [`AppBuilder::record_fps`]) instead of the measured frame time, so the output plays
+    /// back smoothly regardless of how long each frame took to render and write. Rendering
+    /// waits for the writer when it falls a few frames behind.
+    pub fn record(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.record_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the frame rate of recordings. Defaults to 60.
+    pub fn record_fps(mut self, fps: u32) -> Self {
+        self.record_fps = fps.max(1);
+        self
+    }
+
+    /// Also writes recordings as an uncompressed `recording.y4m` video stream.
+    pub fn record_y4m(mut self, enabled: bool) -> Self {
+        self.record_y4m = enabled;
+        self
+    }
+
     pub fn graphics(&mut self) -> &mut GraphicsBuilder {
         &mut self.graphics
     }
@@ -347,17 +459,36 @@ impl AppBuilder {
         self
     }
 
+    /// Runs the application, panicking if it fails to start.
+    ///
+    /// See [`AppBuilder::try_run`] for a version that reports failures as an [`Error`].
     pub fn run<A: App>(self) {
-        tracing_subscriber::fmt()
+        if let Err(e) = self.try_run::<A>() {
+            panic!("Failed to run Soyuz App: {}", e);
+        }
+    }
+
+    /// Runs the application, returning an [`Error`] if the event loop, the window or the
+    /// graphics context could not be created.
+    ///
+    /// Note that winit only allows a single event loop per process, so a failed run cannot
+    /// be retried by calling this method again.
+    pub fn try_run<A: App>(self) -> Result<(), Error> {
+        let _ = tracing_subscriber::fmt()
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-            .init();
+            .try_init();
 
         tracing::info!("Starting Soyuz App...");
 
-        let event_loop = EventLoop::new().unwrap();
+        let event_loop = EventLoop::new()?;
         let mut app_handler = AppHandler::<A>::new(self);
 
-        event_loop.run_app(&mut app_handler).unwrap();
+        event_loop.run_app(&mut app_handler)?;
+
+        match app_handler.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 }
 
@@ -365,8 +496,11 @@ struct AppHandler<A: App> {
     app: Option<A>,
     context: Option<Context>,
     window: Option<Arc<Window>>,
-    last_frame: Option<std::time::Instant>,
+    windows: HashMap<WindowId, Context>,
+    last_frames: HashMap<WindowId, std::time::Instant>,
+    recorder: Option<Recorder>,
     config: AppBuilder,
+    error: Option<Error>,
 }
 
 impl<A: App> AppHandler<A> {
@@ -375,8 +509,118 @@ impl<A: App> AppHandler<A> {
             app: None,
             context: None,
             window: None,
-            last_frame: None,
+            windows: HashMap::new(),
+            last_frames: HashMap::new(),
+            recorder: None,
             config,
+            error: None,
+        }
+    }
+
+    fn init_context(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+
+        let mut context =
+            match pollster::block_on(Context::new(window.clone(), &self.config.graphics)) {
+                Ok(context) => context,
+                Err(e) => {
+                    tracing::error!("Failed to initialize graphics: {}", e);
+                    self.error = Some(e.into());
+                    event_loop.exit();
+                    return;
+                }
+            };
+
+        if let Some(dir) = self.config.record_dir.as_ref() {
+            match Recorder::start(dir, self.config.record_fps, self.config.record_y4m) {
+                Ok(recorder) => {
+                    context.set_recording(true);
+                    self.recorder = Some(recorder);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to start recording: {}", e);
+                    self.error = Some(e.into());
+                    event_loop.exit();
+                    return;
+                }
+            }
+        }
+
+        self.app = Some(A::init(&mut context));
+        self.context = Some(context);
+        window.request_redraw();
+    }
+
+    fn recreate_context(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) else {
+            return;
+        };
+
+        // Release the secondary surfaces first so their windows can get new ones.
+        let windows: Vec<_> = self
+            .windows
+            .drain()
+            .filter_map(|(_, window_ctx)| window_ctx.window)
+            .collect();
+
+        if let Err(e) = pollster::block_on(ctx.recreate()) {
+            tracing::error!("Failed to recreate graphics context: {}", e);
+            self.error = Some(e.into());
+            event_loop.exit();
+            return;
+        }
+        ctx.set_recording(self.recorder.is_some());
+
+        for window in windows {
+            match ctx.with_shared_device(window) {
+                Ok(window_ctx) => {
+                    if let Some(id) = window_ctx.window_id() {
+                        self.windows.insert(id, window_ctx);
+                    }
+                }
+                Err(e) => tracing::error!("Failed to recreate window surface: {}", e),
+            }
+        }
+
+        app.device_recreated(ctx);
+    }
+
+    fn process_window_requests(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) else {
+            return;
+        };
+
+        for request in ctx.take_window_requests() {
+            match request {
+                WindowRequest::Open(attributes) => {
+                    let window = match event_loop.create_window(*attributes) {
+                        Ok(window) => Arc::new(window),
+                        Err(e) => {
+                            tracing::error!("Failed to create window: {}", e);
+                            continue;
+                        }
+                    };
+
+                    match ctx.with_shared_device(window.clone()) {
+                        Ok(mut window_ctx) => {
+                            app.window_opened(&mut window_ctx);
+                            self.windows.insert(window.id(), window_ctx);
+                        }
+                        Err(e) => tracing::error!("Failed to create window surface: {}", e),
+                    }
+                }
+                WindowRequest::Close(id) => {
+                    if ctx.window_id() == Some(id) {
+                        app.cleanup(ctx);
+                        event_loop.exit();
+                    } else if let Some(mut window_ctx) = self.windows.remove(&id) {
+                        app.window_closed(&mut window_ctx);
+                        self.last_frames.remove(&id);
+                    }
+                }
+            }
         }
     }
 }
@@ -386,7 +630,9 @@ impl<A: App> ApplicationHandler for AppHandler<A> {
         if self.window.is_none() {
             let mut window_attributes = winit::window::Window::default_attributes()
                 .with_title(&self.config.title)
-                .with_resizable(self.config.resizable);
+                .with_resizable(self.config.resizable)
+                .with_transparent(self.config.transparent)
+                .with_decorations(self.config.decorations);
 
             if let Some(size) = self.config.size {
                 window_attributes = window_attributes.with_inner_size(size);
@@ -413,6 +659,7 @@ impl<A: App> ApplicationHandler for AppHandler<A> {
                 Ok(window) => Arc::new(window),
                 Err(e) => {
                     tracing::error!("Failed to create window: {}", e);
+                    self.error = Some(e.into());
                     event_loop.exit();
                     return;
                 }
@@ -435,58 +682,79 @@ impl<A: App> ApplicationHandler for AppHandler<A> {
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
+        window_id: WindowId,
         event: WindowEvent,
     ) {
+        if self.context.is_none() {
+            match event {
+                WindowEvent::CloseRequested => event_loop.exit(),
+                WindowEvent::RedrawRequested => self.init_context(event_loop),
+                _ => {}
+            }
+
+            if self.context.is_none() {
+                return;
+            }
+        }
+
+        let is_main = self.window.as_ref().map(|window| window.id()) == Some(window_id);
+        let ctx = if is_main {
+            self.context.as_mut()
+        } else {
+            self.windows.get_mut(&window_id)
+        };
+        let (Some(app), Some(ctx)) = (self.app.as_mut(), ctx) else {
+            return;
+        };
+
         match event {
             WindowEvent::CloseRequested => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
+                if is_main {
                     app.cleanup(ctx);
+                    event_loop.exit();
+                } else {
+                    app.window_closed(ctx);
+                    self.windows.remove(&window_id);
+                    self.last_frames.remove(&window_id);
                 }
-                event_loop.exit();
             }
             WindowEvent::Resized(physical_size) => {
-                if let Some(ctx) = self.context.as_mut() {
-                    ctx.resize(physical_size);
-                    if let Some(app) = self.app.as_mut() {
-                        app.resize(ctx, physical_size.width, physical_size.height);
-                    }
-                }
+                ctx.resize(physical_size);
+                app.resize(ctx, physical_size.width, physical_size.height);
             }
             WindowEvent::RedrawRequested => {
-                if self.context.is_none() {
-                    let window = self.window.as_ref().expect("Window should exist");
-                    let context =
-                        pollster::block_on(Context::new(window.clone(), &self.config.graphics));
-                    self.context = Some(context);
-
-                    if let Some(ctx) = self.context.as_mut() {
-                        let app = A::init(ctx);
-                        self.app = Some(app);
-                    }
-
-                    if let Some(window) = self.window.as_ref() {
-                        window.request_redraw();
-                    }
+                if ctx.is_device_lost() {
+                    return;
                 }
 
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    let now = std::time::Instant::now();
-                    let dt = if let Some(last_frame) = self.last_frame {
-                        now.duration_since(last_frame).as_secs_f32()
-                    } else {
-                        0.016
-                    };
-                    self.last_frame = Some(now);
+                let now = std::time::Instant::now();
+                let last_frame = self.last_frames.insert(window_id, now);
+                let dt = if self.recorder.is_some() {
+                    1.0 / self.config.record_fps as f32
+                } else if let Some(last_frame) = last_frame {
+                    now.duration_since(last_frame).as_secs_f32()
+                } else {
+                    0.016
+                };
+
+                app.frame(ctx, dt);
 
-                    app.frame(ctx, dt);
+                if let Some(recorder) = self.recorder.as_mut()
+                    && is_main
+                {
+                    recorder.push(ctx.take_recorded_frames());
                 }
             }
 
             WindowEvent::KeyboardInput { event, .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut())
-                    && let winit::keyboard::PhysicalKey::Code(key_code) = event.physical_key
-                {
+                if let winit::keyboard::PhysicalKey::Code(key_code) = event.physical_key {
+                    if event.state.is_pressed()
+                        && !event.repeat
+                        && self.config.screenshot_key == Some(key_code)
+                    {
+                        capture_screenshot(ctx, &self.config.screenshot_dir);
+                    }
+
                     if event.state.is_pressed() {
                         app.key_pressed(ctx, key_code);
                     } else {
@@ -496,100 +764,102 @@ impl<A: App> ApplicationHandler for AppHandler<A> {
             }
 
             WindowEvent::Ime(winit::event::Ime::Commit(text)) => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.text_input(ctx, &text);
-                }
+                app.text_input(ctx, &text);
             }
 
             WindowEvent::CursorMoved { position, .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.mouse_moved(ctx, position.x, position.y);
-                }
+                app.mouse_moved(ctx, position.x, position.y);
             }
 
             WindowEvent::MouseInput { state, button, .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    if state.is_pressed() {
-                        app.mouse_pressed(ctx, button);
-                    } else {
-                        app.mouse_released(ctx, button);
-                    }
+                if state.is_pressed() {
+                    app.mouse_pressed(ctx, button);
+                } else {
+                    app.mouse_released(ctx, button);
                 }
             }
 
             WindowEvent::MouseWheel { delta, .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    let (delta_x, delta_y) = match delta {
-                        winit::event::MouseScrollDelta::LineDelta(x, y) => (x, y),
-                        winit::event::MouseScrollDelta::PixelDelta(pos) => {
-                            (pos.x as f32, pos.y as f32)
-                        }
-                    };
-                    app.mouse_scrolled(ctx, delta_x, delta_y);
-                }
+                let (delta_x, delta_y) = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    winit::event::MouseScrollDelta::PixelDelta(pos) => (pos.x as f32, pos.y as f32),
+                };
+                app.mouse_scrolled(ctx, delta_x, delta_y);
             }
 
             WindowEvent::CursorEntered { .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.cursor_entered(ctx);
-                }
+                app.cursor_entered(ctx);
             }
 
             WindowEvent::CursorLeft { .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.cursor_left(ctx);
-                }
+                app.cursor_left(ctx);
             }
 
-            WindowEvent::Touch(touch) => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    match touch.phase {
-                        winit::event::TouchPhase::Started => {
-                            app.touch_started(ctx, touch.id, touch.location.x, touch.location.y);
-                        }
-                        winit::event::TouchPhase::Moved => {
-                            app.touch_moved(ctx, touch.id, touch.location.x, touch.location.y);
-                        }
-                        winit::event::TouchPhase::Ended => {
-                            app.touch_ended(ctx, touch.id, touch.location.x, touch.location.y);
-                        }
-                        winit::event::TouchPhase::Cancelled => {
-                            app.touch_cancelled(ctx, touch.id, touch.location.x, touch.location.y);
-                        }
-                    }
+            WindowEvent::Touch(touch) => match touch.phase {
+                winit::event::TouchPhase::Started => {
+                    app.touch_started(ctx, touch.id, touch.location.x, touch.location.y);
                 }
-            }
+                winit::event::TouchPhase::Moved => {
+                    app.touch_moved(ctx, touch.id, touch.location.x, touch.location.y);
+                }
+                winit::event::TouchPhase::Ended => {
+                    app.touch_ended(ctx, touch.id, touch.location.x, touch.location.y);
+                }
+                winit::event::TouchPhase::Cancelled => {
+                    app.touch_cancelled(ctx, touch.id, touch.location.x, touch.location.y);
+                }
+            },
 
             WindowEvent::Focused(focused) => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    if focused {
-                        app.focused(ctx);
-                    } else {
-                        app.unfocused(ctx);
-                    }
+                if focused {
+                    app.focused(ctx);
+                } else {
+                    app.unfocused(ctx);
                 }
             }
 
             WindowEvent::Moved(position) => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.moved(ctx, position.x, position.y);
-                }
+                app.moved(ctx, position.x, position.y);
             }
 
             WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                if let (Some(app), Some(ctx)) = (self.app.as_mut(), self.context.as_mut()) {
-                    app.scale_factor_changed(ctx, scale_factor);
-                }
+                app.scale_factor_changed(ctx, scale_factor);
             }
 
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        let Some(ctx) = self.context.as_mut() else {
+            return;
+        };
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            ctx.set_recording(false);
+            recorder.push(ctx.take_recorded_frames());
+            recorder.finish();
+        }
+
+        ctx.save_pipeline_cache();
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.context.as_ref().is_some_and(Context::is_device_lost) {
+            self.recreate_context(event_loop);
+        }
+
+        self.process_window_requests(event_loop);
+
         if let Some(window) = self.window.as_ref() {
             window.request_redraw();
         }
+
+        for ctx in self.windows.values() {
+            if let Some(window) = ctx.window.as_ref() {
+                window.request_redraw();
+            }
+        }
     }
 }
 
@@ -600,3 +870,21 @@ pub fn builder() -> AppBuilder {
 pub fn run<A: App>(title: &str) {
     builder().title(title).run::<A>();
 }
+
+/// Saves the next frame of `ctx` to a timestamped PNG file in `dir`.
+fn capture_screenshot(ctx: &mut Context, dir: &Path) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        tracing::error!(
+            "Failed to create screenshot directory {}: {}",
+            dir.display(),
+            e
+        );
+        return;
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    ctx.save_screenshot(dir.join(format!("screenshot-{}.png", timestamp)));
+}
@@ -0,0 +1,15 @@
+/// Errors that can stop a Soyuz application from starting or running.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("event loop error: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+
+    #[error("failed to create window: {0}")]
+    Window(#[from] winit::error::OsError),
+
+    #[error("failed to initialize graphics: {0}")]
+    Graphics(#[from] soyuz_gfx::Error),
+
+    #[error("failed to start recording: {0}")]
+    Recording(#[from] std::io::Error),
+}
@@ -1,11 +1,20 @@
-pub use soyuz_gfx::{Context, GraphicsBuilder, RenderPassBuilder};
+pub use soyuz_gfx::{
+    AdapterSelector, BindGroupBuilder, BindGroupLayoutBuilder, Buffer, ComputePassBuilder,
+    ComputePassExt, ComputePipelineBuilder, Context, FrameOutcome, GraphicsBuilder, IndexType,
+    LimitsPolicy, MipmapGenerator, RenderPassBuilder, RenderPassExt, Screenshot, Texture,
+    TextureBuilder, Uniform,
+};
 
+pub use bytemuck;
 pub use wgpu;
 pub use winit;
 
 pub mod app;
+mod error;
+mod record;
 
 pub use app::{AppBuilder, builder, run};
+pub use error::Error;
 
 pub mod prelude {
     pub use crate::wgpu;
@@ -13,7 +22,9 @@ pub mod prelude {
     pub use crate::winit;
     pub use winit::keyboard::KeyCode;
 
-    pub use crate::Context;
     pub use crate::app::*;
     pub use crate::{AppBuilder, GraphicsBuilder, builder, run};
+    pub use crate::{
+        Buffer, ComputePassExt, Context, FrameOutcome, RenderPassExt, Texture, Uniform,
+    };
 }
@@ -0,0 +1,207 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use soyuz_gfx::Screenshot;
+
+/// Number of frames waiting to be written before [`Recorder::push`] blocks.
+const MAX_QUEUED_FRAMES: usize = 4;
+
+/// Writes recorded frames to disk on a background thread so encoding overlaps rendering.
+///
+/// At most [`MAX_QUEUED_FRAMES`] frames wait to be written; when the writer falls further
+/// behind, pushing blocks the render loop until it catches up.
+pub(crate) struct Recorder {
+    sender: Option<mpsc::SyncSender<Screenshot>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Starts writing frames to `dir` as a numbered PNG sequence, and to `dir/recording.y4m`
+    /// as well when `y4m` is set.
+    pub(crate) fn start(dir: &Path, fps: u32, y4m: bool) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut writer = FrameWriter {
+            dir: dir.to_path_buf(),
+            fps,
+            y4m: y4m.then(|| dir.join("recording.y4m")),
+            y4m_file: None,
+            y4m_size: None,
+            index: 0,
+        };
+
+        let (sender, receiver) = mpsc::sync_channel::<Screenshot>(MAX_QUEUED_FRAMES);
+        let thread = std::thread::Builder::new()
+            .name("soyuz-recorder".to_string())
+            .spawn(move || {
+                for frame in receiver {
+                    writer.write(&frame);
+                }
+                writer.finish();
+            })?;
+
+        tracing::info!("Recording frames to {} at {} fps", dir.display(), fps);
+
+        Ok(Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues `frames` for writing, waiting for the writer if too many are queued.
+    pub(crate) fn push(&mut self, frames: Vec<Screenshot>) {
+        let Some(sender) = self.sender.as_ref() else {
+            return;
+        };
+
+        for frame in frames {
+            if sender.send(frame).is_err() {
+                tracing::error!("Recorder thread stopped, dropping recorded frames");
+                self.sender = None;
+                return;
+            }
+        }
+    }
+
+    /// Waits for every queued frame to be written.
+    pub(crate) fn finish(&mut self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            tracing::error!("Recorder thread panicked");
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+struct FrameWriter {
+    dir: PathBuf,
+    fps: u32,
+    y4m: Option<PathBuf>,
+    y4m_file: Option<BufWriter<File>>,
+    y4m_size: Option<(u32, u32)>,
+    index: u64,
+}
+
+impl FrameWriter {
+    fn write(&mut self, frame: &Screenshot) {
+        self.index += 1;
+
+        let path = self.dir.join(format!("frame-{:06}.png", self.index));
+        if let Err(e) = frame.save_png(&path) {
+            tracing::error!("Failed to write {}: {}", path.display(), e);
+        }
+
+        if self.y4m.is_some()
+            && let Err(e) = self.write_y4m(frame)
+        {
+            tracing::error!("Failed to write Y4M frame, disabling Y4M output: {}", e);
+            self.y4m = None;
+            self.y4m_file = None;
+        }
+    }
+
+    fn write_y4m(&mut self, frame: &Screenshot) -> std::io::Result<()> {
+        let Some(path) = self.y4m.as_ref() else {
+            return Ok(());
+        };
+
+        let size = (frame.width, frame.height);
+        match self.y4m_size {
+            Some(y4m_size) if y4m_size != size => {
+                tracing::warn!(
+                    "Skipping {}x{} frame in {}x{} Y4M stream",
+                    size.0,
+                    size.1,
+                    y4m_size.0,
+                    y4m_size.1
+                );
+                return Ok(());
+            }
+            Some(_) => {}
+            None => {
+                let mut file = BufWriter::new(File::create(path)?);
+                writeln!(
+                    file,
+                    "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                    size.0, size.1, self.fps
+                )?;
+                self.y4m_file = Some(file);
+                self.y4m_size = Some(size);
+            }
+        }
+
+        let Some(file) = self.y4m_file.as_mut() else {
+            return Ok(());
+        };
+
+        file.write_all(b"FRAME\n")?;
+        let [y, u, v] = rgba_to_yuv444(&frame.pixels);
+        file.write_all(&y)?;
+        file.write_all(&u)?;
+        file.write_all(&v)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        if let Some(file) = self.y4m_file.as_mut()
+            && let Err(e) = file.flush()
+        {
+            tracing::error!("Failed to flush Y4M stream: {}", e);
+        }
+
+        tracing::info!("Recorded {} frames to {}", self.index, self.dir.display());
+    }
+}
+
+/// Converts sRGB RGBA pixels to limited range BT.709 Y, Cb and Cr planes.
+fn rgba_to_yuv444(pixels: &[u8]) -> [Vec<u8>; 3] {
+    let count = pixels.len() / 4;
+    let mut planes = [
+        Vec::with_capacity(count),
+        Vec::with_capacity(count),
+        Vec::with_capacity(count),
+    ];
+
+    for pixel in pixels.chunks_exact(4) {
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| f32::from(c) / 255.0);
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let cb = (b - y) / 1.8556;
+        let cr = (r - y) / 1.5748;
+
+        planes[0].push((16.0 + 219.0 * y).round() as u8);
+        planes[1].push((128.0 + 224.0 * cb).round() as u8);
+        planes[2].push((128.0 + 224.0 * cr).round() as u8);
+    }
+
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_rgba_to_limited_range_yuv() {
+        let pixels = [
+            0, 0, 0, 255, //
+            255, 255, 255, 255, //
+            255, 0, 0, 255, //
+            0, 0, 255, 0,
+        ];
+        let [y, u, v] = rgba_to_yuv444(&pixels);
+
+        assert_eq!(y, [16, 235, 63, 32]);
+        assert_eq!(u, [128, 128, 102, 240]);
+        assert_eq!(v, [128, 128, 240, 118]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Strategy used by [`GraphicsBuilder`](crate::GraphicsBuilder) to pick an adapter explicitly
+/// instead of relying on the power preference.
+#[derive(Clone)]
+pub enum AdapterSelector {
+    /// The adapter at this position in [`enumerate_adapters`] order.
+    Index(usize),
+    /// The first adapter whose name contains this substring, ignoring case.
+    Name(String),
+    /// The first adapter of this device type.
+    DeviceType(wgpu::DeviceType),
+    /// The first adapter for which the predicate returns `true`.
+    Predicate(Arc<dyn Fn(&wgpu::AdapterInfo) -> bool + Send + Sync>),
+}
+
+impl AdapterSelector {
+    pub fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            AdapterSelector::Index(i) => *i == index,
+            AdapterSelector::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            AdapterSelector::DeviceType(device_type) => info.device_type == *device_type,
+            AdapterSelector::Predicate(predicate) => predicate(info),
+        }
+    }
+}
+
+impl fmt::Debug for AdapterSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdapterSelector::Index(i) => f.debug_tuple("Index").field(i).finish(),
+            AdapterSelector::Name(name) => f.debug_tuple("Name").field(name).finish(),
+            AdapterSelector::DeviceType(device_type) => {
+                f.debug_tuple("DeviceType").field(device_type).finish()
+            }
+            AdapterSelector::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+/// Lists the adapters available on the given backends, in the order used by
+/// [`AdapterSelector::Index`].
+pub fn enumerate_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+
+    instance
+        .enumerate_adapters(backends)
+        .iter()
+        .map(wgpu::Adapter::get_info)
+        .collect()
+}
+
+pub(crate) fn select_adapter(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    selector: &AdapterSelector,
+    compatible_surface: Option<&wgpu::Surface<'_>>,
+) -> Result<wgpu::Adapter, Vec<wgpu::AdapterInfo>> {
+    let adapters = instance.enumerate_adapters(backends);
+    let available = adapters.iter().map(wgpu::Adapter::get_info).collect();
+
+    adapters
+        .into_iter()
+        .enumerate()
+        .filter(|(_, adapter)| compatible_surface.is_none_or(|s| adapter.is_surface_supported(s)))
+        .find(|(index, adapter)| selector.matches(*index, &adapter.get_info()))
+        .map(|(_, adapter)| adapter)
+        .ok_or(available)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, device_type: wgpu::DeviceType) -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    /// Returns the index of the first adapter matching `selector`, as `select_adapter` does.
+    fn select(selector: &AdapterSelector, adapters: &[wgpu::AdapterInfo]) -> Option<usize> {
+        adapters
+            .iter()
+            .enumerate()
+            .position(|(index, info)| selector.matches(index, info))
+    }
+
+    fn adapters() -> Vec<wgpu::AdapterInfo> {
+        vec![
+            info("Intel(R) UHD Graphics 630", wgpu::DeviceType::IntegratedGpu),
+            info("NVIDIA GeForce RTX 3070", wgpu::DeviceType::DiscreteGpu),
+            info("llvmpipe (LLVM 17.0.6, 256 bits)", wgpu::DeviceType::Cpu),
+            info("NVIDIA GeForce GTX 1050", wgpu::DeviceType::DiscreteGpu),
+        ]
+    }
+
+    #[test]
+    fn selects_by_index() {
+        assert_eq!(select(&AdapterSelector::Index(2), &adapters()), Some(2));
+        assert_eq!(select(&AdapterSelector::Index(4), &adapters()), None);
+    }
+
+    #[test]
+    fn selects_first_name_match_ignoring_case() {
+        let selector = AdapterSelector::Name("nvidia".to_string());
+        assert_eq!(select(&selector, &adapters()), Some(1));
+
+        let selector = AdapterSelector::Name("LLVMPIPE".to_string());
+        assert_eq!(select(&selector, &adapters()), Some(2));
+
+        let selector = AdapterSelector::Name("Radeon".to_string());
+        assert_eq!(select(&selector, &adapters()), None);
+    }
+
+    #[test]
+    fn selects_first_of_device_type() {
+        let selector = AdapterSelector::DeviceType(wgpu::DeviceType::DiscreteGpu);
+        assert_eq!(select(&selector, &adapters()), Some(1));
+
+        let selector = AdapterSelector::DeviceType(wgpu::DeviceType::VirtualGpu);
+        assert_eq!(select(&selector, &adapters()), None);
+    }
+
+    #[test]
+    fn selects_by_predicate() {
+        let selector = AdapterSelector::Predicate(Arc::new(|info| info.name.ends_with("1050")));
+        assert_eq!(select(&selector, &adapters()), Some(3));
+
+        let selector = AdapterSelector::Predicate(Arc::new(|_| false));
+        assert_eq!(select(&selector, &adapters()), None);
+        assert_eq!(select(&AdapterSelector::Index(0), &[]), None);
+    }
+}
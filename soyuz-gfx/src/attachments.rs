@@ -0,0 +1,120 @@
+/// Returns the largest sample count up to `requested` that `format` supports for
+/// multisampled rendering with resolve on this adapter and device.
+pub(crate) fn select_sample_count(
+    requested: u32,
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> u32 {
+    if requested <= 1 {
+        return 1;
+    }
+
+    let features = if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(device.features())
+    };
+
+    let supported = |count: u32| {
+        count == 1
+            || (features.flags.sample_count_supported(count)
+                && features
+                    .flags
+                    .contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE))
+    };
+
+    let count = [16, 8, 4, 2, 1]
+        .into_iter()
+        .find(|&count| count <= requested && supported(count))
+        .unwrap_or(1);
+
+    if count != requested {
+        tracing::warn!(
+            "{}x MSAA is not supported for {:?}, using {}x instead",
+            requested,
+            format,
+            count
+        );
+    }
+
+    count
+}
+
+pub(crate) fn create_msaa_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Validates the requested depth format against the device, falling back to
+/// `Depth24PlusStencil8` when the format needs a feature the device lacks.
+pub(crate) fn select_depth_format(
+    requested: Option<wgpu::TextureFormat>,
+    device: &wgpu::Device,
+) -> Option<wgpu::TextureFormat> {
+    let format = requested?;
+
+    if !format.has_depth_aspect() {
+        tracing::warn!(
+            "{:?} is not a depth format, no depth buffer created",
+            format
+        );
+        return None;
+    }
+
+    if !device.features().contains(format.required_features()) {
+        tracing::warn!(
+            "{:?} requires {:?}, using Depth24PlusStencil8 instead",
+            format,
+            format.required_features()
+        );
+        return Some(wgpu::TextureFormat::Depth24PlusStencil8);
+    }
+
+    Some(format)
+}
+
+pub(crate) fn create_depth_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Buffer"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
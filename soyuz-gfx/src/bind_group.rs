@@ -0,0 +1,174 @@
+/// Builds a `wgpu::BindGroupLayout` from shorthand entries.
+///
+/// Each entry names its binding index and the shader stages that can access it.
+pub struct BindGroupLayoutBuilder<'a> {
+    device: &'a wgpu::Device,
+    entries: Vec<wgpu::BindGroupLayoutEntry>,
+    label: Option<&'a str>,
+}
+
+impl<'a> BindGroupLayoutBuilder<'a> {
+    pub fn new(device: &'a wgpu::Device) -> Self {
+        Self {
+            device,
+            entries: Vec::new(),
+            label: None,
+        }
+    }
+
+    /// Adds an entry that the shorthand methods do not cover.
+    pub fn entry(mut self, entry: wgpu::BindGroupLayoutEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    fn binding(self, binding: u32, visibility: wgpu::ShaderStages, ty: wgpu::BindingType) -> Self {
+        self.entry(wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: None,
+        })
+    }
+
+    /// Adds a uniform buffer (`var<uniform>`).
+    pub fn uniform(self, binding: u32, visibility: wgpu::ShaderStages) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        )
+    }
+
+    /// Adds a storage buffer, `var<storage, read>` if `read_only` and
+    /// `var<storage, read_write>` otherwise.
+    pub fn storage(self, binding: u32, visibility: wgpu::ShaderStages, read_only: bool) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        )
+    }
+
+    /// Adds a filterable float 2D texture (`texture_2d<f32>`).
+    pub fn texture(self, binding: u32, visibility: wgpu::ShaderStages) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+        )
+    }
+
+    /// Adds a 2D storage texture (`texture_storage_2d<format, access>`).
+    pub fn storage_texture(
+        self,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+        format: wgpu::TextureFormat,
+        access: wgpu::StorageTextureAccess,
+    ) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::StorageTexture {
+                access,
+                format,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+        )
+    }
+
+    /// Adds a filtering sampler (`sampler`).
+    pub fn sampler(self, binding: u32, visibility: wgpu::ShaderStages) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        )
+    }
+
+    /// Adds a comparison sampler (`sampler_comparison`), as used for shadow maps.
+    pub fn comparison_sampler(self, binding: u32, visibility: wgpu::ShaderStages) -> Self {
+        self.binding(
+            binding,
+            visibility,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+        )
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn build(self) -> wgpu::BindGroupLayout {
+        self.device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: self.label,
+                entries: &self.entries,
+            })
+    }
+}
+
+/// Builds a `wgpu::BindGroup` for a layout, one resource per binding.
+pub struct BindGroupBuilder<'a> {
+    device: &'a wgpu::Device,
+    layout: &'a wgpu::BindGroupLayout,
+    entries: Vec<wgpu::BindGroupEntry<'a>>,
+    label: Option<&'a str>,
+}
+
+impl<'a> BindGroupBuilder<'a> {
+    pub fn new(device: &'a wgpu::Device, layout: &'a wgpu::BindGroupLayout) -> Self {
+        Self {
+            device,
+            layout,
+            entries: Vec::new(),
+            label: None,
+        }
+    }
+
+    pub fn resource(mut self, binding: u32, resource: wgpu::BindingResource<'a>) -> Self {
+        self.entries
+            .push(wgpu::BindGroupEntry { binding, resource });
+        self
+    }
+
+    /// Binds the whole of `buffer`.
+    pub fn buffer(self, binding: u32, buffer: &'a wgpu::Buffer) -> Self {
+        self.resource(binding, buffer.as_entire_binding())
+    }
+
+    pub fn texture(self, binding: u32, view: &'a wgpu::TextureView) -> Self {
+        self.resource(binding, wgpu::BindingResource::TextureView(view))
+    }
+
+    pub fn sampler(self, binding: u32, sampler: &'a wgpu::Sampler) -> Self {
+        self.resource(binding, wgpu::BindingResource::Sampler(sampler))
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn build(self) -> wgpu::BindGroup {
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: self.label,
+            layout: self.layout,
+            entries: &self.entries,
+        })
+    }
+}
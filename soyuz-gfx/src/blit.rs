@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use wgpu::util::DeviceExt;
+
+use crate::{RenderPassBuilder, RenderPipelineBuilder, Shader};
+
+/// Copies a texture view into a render target of any format with a fullscreen triangle.
+///
+/// Pipelines are created lazily and cached per target format.
+pub(crate) struct Blitter {
+    shader: Shader,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    array: bool,
+}
+
+impl Blitter {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        Self::with_source_dimension(device, wgpu::TextureViewDimension::D2)
+    }
+
+    /// Creates a blitter whose sources are `D2Array` views covering every layer, copied
+    /// from with [`Blitter::blit_layer`]. Some backends cannot bind sampled views of a
+    /// single layer other than the first.
+    pub(crate) fn new_array(device: &wgpu::Device) -> Self {
+        Self::with_source_dimension(device, wgpu::TextureViewDimension::D2Array)
+    }
+
+    fn with_source_dimension(
+        device: &wgpu::Device,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> Self {
+        let array = view_dimension == wgpu::TextureViewDimension::D2Array;
+        let (source_type, source_layer, layer_binding) = if array {
+            (
+                "texture_2d_array<f32>",
+                ", src_layer",
+                "@group(0) @binding(2)\nvar<uniform> src_layer: u32;",
+            )
+        } else {
+            ("texture_2d<f32>", "", "")
+        };
+        let source = include_str!("blit.wgsl")
+            .replace("SOURCE_TYPE", source_type)
+            .replace("SOURCE_LAYER", source_layer)
+            .replace("LAYER_BINDING", layer_binding);
+        let shader = Shader::from_wgsl(device, &source);
+
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+        if array {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+        }
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blit Bind Group Layout"),
+            entries: &entries,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blit Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            sampler,
+            pipelines: HashMap::new(),
+            array,
+        }
+    }
+
+    pub(crate) fn blit(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        target_format: wgpu::TextureFormat,
+    ) {
+        debug_assert!(!self.array, "array blitters copy with `blit_layer`");
+        self.draw(device, encoder, source, None, target, target_format);
+    }
+
+    /// Copies layer `layer` of the `D2Array` view `source` into `target`.
+    pub(crate) fn blit_layer(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        layer: u32,
+        target: &wgpu::TextureView,
+        target_format: wgpu::TextureFormat,
+    ) {
+        debug_assert!(self.array, "only array blitters copy single layers");
+        // Padded to the 16 byte alignment of uniform buffers.
+        let layer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Blit Layer Buffer"),
+            contents: bytemuck::bytes_of(&[layer, 0, 0, 0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        self.draw(device, encoder, source, Some(&layer), target, target_format);
+    }
+
+    fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        layer: Option<&wgpu::Buffer>,
+        target: &wgpu::TextureView,
+        target_format: wgpu::TextureFormat,
+    ) {
+        let pipeline = self.pipelines.entry(target_format).or_insert_with(|| {
+            RenderPipelineBuilder::new(device, target_format)
+                .shader(self.shader.module())
+                .pipeline_layout(&self.pipeline_layout)
+                .label("Blit Pipeline")
+                .build()
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(source),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+        ];
+        if let Some(layer) = layer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2,
+                resource: layer.as_entire_binding(),
+            });
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blit Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        });
+
+        let mut pass = RenderPassBuilder::new(encoder, target)
+            .label("Blit Pass")
+            .begin();
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
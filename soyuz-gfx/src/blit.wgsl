@@ -0,0 +1,29 @@
+// The placeholders in capitals are replaced to sample either a 2D view or the layer of a
+// 2D array view given by the `src_layer` uniform.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var src_texture: SOURCE_TYPE;
+@group(0) @binding(1)
+var src_sampler: sampler;
+LAYER_BINDING
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(src_texture, src_sampler, in.uv SOURCE_LAYER);
+}
@@ -0,0 +1,207 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use bytemuck::Pod;
+use wgpu::util::DeviceExt;
+
+/// A GPU buffer holding `len` elements of type `T`.
+///
+/// Buffers are always created with `COPY_DST` so they can be updated with
+/// [`Buffer::write`] and [`Buffer::write_range`].
+#[derive(Debug, Clone)]
+pub struct Buffer<T: Pod> {
+    buffer: wgpu::Buffer,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Buffer<T> {
+    /// Creates a buffer initialized with `data`.
+    pub fn new(
+        device: &wgpu::Device,
+        label: Option<&str>,
+        data: &[T],
+        usage: wgpu::BufferUsages,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label,
+            contents: bytemuck::cast_slice(data),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            buffer,
+            len: data.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a zero-initialized buffer with room for `len` elements.
+    pub fn zeroed(
+        device: &wgpu::Device,
+        label: Option<&str>,
+        len: usize,
+        usage: wgpu::BufferUsages,
+    ) -> Self {
+        let size = (len * size_of::<T>()) as u64;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label,
+            size: size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            buffer,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of the elements in bytes, excluding alignment padding.
+    pub fn size(&self) -> wgpu::BufferAddress {
+        (self.len * size_of::<T>()) as wgpu::BufferAddress
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn usage(&self) -> wgpu::BufferUsages {
+        self.buffer.usage()
+    }
+
+    /// Returns a slice covering every element, as taken by `RenderPass::set_vertex_buffer`
+    /// and `RenderPass::set_index_buffer`, or `None` if the buffer is empty since wgpu
+    /// cannot slice empty ranges.
+    pub fn slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+        (!self.is_empty()).then(|| self.buffer.slice(..self.size()))
+    }
+
+    /// Returns a binding covering every element, for bind group entries.
+    pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+
+    /// Overwrites the buffer from its first element.
+    ///
+    /// # Panics
+    ///
+    /// See [`Buffer::write_range`].
+    pub fn write(&self, queue: &wgpu::Queue, data: &[T]) {
+        self.write_range(queue, 0, data);
+    }
+
+    /// Overwrites the elements starting at index `start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the elements do not fit in the buffer, or, for elements smaller than four
+    /// bytes, if the write does not start on a four-byte boundary or ends unaligned before
+    /// the last element.
+    pub fn write_range(&self, queue: &wgpu::Queue, start: usize, data: &[T]) {
+        let end = start + data.len();
+        assert!(
+            end <= self.len,
+            "write of elements {}..{} out of bounds for a buffer of {} elements",
+            start,
+            end,
+            self.len
+        );
+
+        let offset = (start * size_of::<T>()) as wgpu::BufferAddress;
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        assert!(
+            offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "buffer writes must start on a {} byte boundary",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        if (bytes.len() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            queue.write_buffer(&self.buffer, offset, bytes);
+        } else {
+            // The buffer is padded to the copy alignment, so a write ending at the last
+            // element can be padded too.
+            assert!(
+                end == self.len,
+                "buffer writes must end on a {} byte boundary",
+                wgpu::COPY_BUFFER_ALIGNMENT
+            );
+            let mut padded = bytes.to_vec();
+            padded.resize(
+                bytes
+                    .len()
+                    .next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize),
+                0,
+            );
+            queue.write_buffer(&self.buffer, offset, &padded);
+        }
+    }
+}
+
+/// Element types usable in index buffers.
+pub trait IndexType: Pod {
+    const FORMAT: wgpu::IndexFormat;
+}
+
+impl IndexType for u16 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+}
+
+impl IndexType for u32 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint32;
+}
+
+impl<T: IndexType> Buffer<T> {
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        T::FORMAT
+    }
+}
+
+/// Binds and draws typed [`Buffer`]s in a render pass.
+///
+/// Empty buffers cannot be bound, so the methods do nothing when given one.
+pub trait RenderPassExt {
+    fn set_typed_vertex_buffer<T: Pod>(&mut self, slot: u32, buffer: &Buffer<T>);
+
+    fn set_typed_index_buffer<T: IndexType>(&mut self, buffer: &Buffer<T>);
+
+    /// Binds `indices` and draws all of them for the given instances.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer holds more than `u32::MAX` indices.
+    fn draw_indexed_buffer<T: IndexType>(&mut self, indices: &Buffer<T>, instances: Range<u32>);
+}
+
+impl RenderPassExt for wgpu::RenderPass<'_> {
+    fn set_typed_vertex_buffer<T: Pod>(&mut self, slot: u32, buffer: &Buffer<T>) {
+        if let Some(slice) = buffer.slice() {
+            self.set_vertex_buffer(slot, slice);
+        }
+    }
+
+    fn set_typed_index_buffer<T: IndexType>(&mut self, buffer: &Buffer<T>) {
+        if let Some(slice) = buffer.slice() {
+            self.set_index_buffer(slice, T::FORMAT);
+        }
+    }
+
+    fn draw_indexed_buffer<T: IndexType>(&mut self, indices: &Buffer<T>, instances: Range<u32>) {
+        if indices.is_empty() {
+            return;
+        }
+        let count = u32::try_from(indices.len()).expect("index count exceeds u32::MAX");
+        self.set_typed_index_buffer(indices);
+        self.draw_indexed(0..count, 0, instances);
+    }
+}
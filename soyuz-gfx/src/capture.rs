@@ -0,0 +1,289 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// Number of recorded frames whose readback may be in flight before rendering waits for
+/// the oldest one.
+pub(crate) const MAX_PENDING_READBACKS: usize = 3;
+
+use crate::{Error, Result};
+
+/// A frame read back from the GPU as tightly packed 8-bit sRGB RGBA pixels.
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Encodes the screenshot as a PNG file at `path`.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        image::save_buffer_with_format(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+            image::ImageFormat::Png,
+        )?;
+        Ok(())
+    }
+}
+
+/// What to do with the next frame captured by [`crate::Context::render`].
+#[derive(Debug, Clone)]
+pub(crate) enum CaptureRequest {
+    /// Keep the screenshot until it is taken with `Context::take_capture()`.
+    Keep,
+    /// Save the screenshot as a PNG file.
+    Save(PathBuf),
+}
+
+/// Returns whether frames of `format` can be converted to a [`Screenshot`].
+pub(crate) fn is_supported_format(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+            | wgpu::TextureFormat::Bgra8UnormSrgb
+            | wgpu::TextureFormat::Rgba16Float
+            | wgpu::TextureFormat::Rgb10a2Unorm
+    )
+}
+
+/// A pending copy of a texture into a mappable buffer.
+pub(crate) struct Readback {
+    buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    mapped: Option<mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl Readback {
+    /// Records a copy of `texture` into a new readback buffer.
+    ///
+    /// Rows are padded to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`] as required for
+    /// texture-to-buffer copies; the padding is stripped again on readback.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Result<Self> {
+        let format = texture.format();
+        if !is_supported_format(format) {
+            return Err(Error::UnsupportedCaptureFormat(format));
+        }
+
+        let width = texture.width();
+        let height = texture.height();
+        let bytes_per_pixel = format.block_copy_size(None).unwrap_or(4);
+        let padded_bytes_per_row =
+            (width * bytes_per_pixel).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        Ok(Self {
+            buffer,
+            format,
+            width,
+            height,
+            padded_bytes_per_row,
+            mapped: None,
+        })
+    }
+
+    /// Starts mapping the buffer without waiting for the copy to complete.
+    ///
+    /// The encoder passed to [`Readback::new`] must have been submitted.
+    pub(crate) fn map(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.mapped = Some(receiver);
+    }
+
+    /// Returns the screenshot if the buffer has been mapped, or `None` while the copy is
+    /// still in flight. The device must be polled for mapping to progress.
+    pub(crate) fn try_finish(&mut self) -> Option<Result<Screenshot>> {
+        let receiver = self.mapped.as_ref()?;
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => Err(wgpu::BufferAsyncError),
+        };
+        self.mapped = None;
+
+        Some(result.map(|()| self.read()).map_err(Error::from))
+    }
+
+    /// Maps the buffer, blocking until the copy has completed, and converts its contents.
+    ///
+    /// The encoder passed to [`Readback::new`] must have been submitted.
+    pub(crate) fn wait(&mut self, device: &wgpu::Device) -> Result<Screenshot> {
+        if self.mapped.is_none() {
+            self.map();
+        }
+        device.poll(wgpu::PollType::wait_indefinitely())?;
+
+        self.try_finish()
+            .unwrap_or(Err(Error::BufferMap(wgpu::BufferAsyncError)))
+    }
+
+    fn read(&self) -> Screenshot {
+        let data = self.buffer.slice(..).get_mapped_range();
+        let bytes_per_pixel = self.format.block_copy_size(None).unwrap_or(4) as usize;
+        let row_bytes = self.width as usize * bytes_per_pixel;
+
+        let mut pixels = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for row in data.chunks(self.padded_bytes_per_row as usize) {
+            convert_row(self.format, &row[..row_bytes], &mut pixels);
+        }
+        drop(data);
+        self.buffer.unmap();
+
+        Screenshot {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+}
+
+/// Appends one row of `format` texels to `out` as 8-bit sRGB RGBA.
+fn convert_row(format: wgpu::TextureFormat, row: &[u8], out: &mut Vec<u8>) {
+    match format {
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            for texel in row.chunks_exact(4) {
+                out.extend_from_slice(&[texel[2], texel[1], texel[0], texel[3]]);
+            }
+        }
+        wgpu::TextureFormat::Rgba16Float => {
+            for texel in row.chunks_exact(8) {
+                let channel =
+                    |i: usize| f16_to_f32(u16::from_le_bytes([texel[i * 2], texel[i * 2 + 1]]));
+                out.extend_from_slice(&[
+                    linear_to_srgb(channel(0)),
+                    linear_to_srgb(channel(1)),
+                    linear_to_srgb(channel(2)),
+                    unorm_to_u8(channel(3)),
+                ]);
+            }
+        }
+        wgpu::TextureFormat::Rgb10a2Unorm => {
+            for texel in row.chunks_exact(4) {
+                let packed = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+                out.extend_from_slice(&[
+                    ((packed >> 2) & 0xff) as u8,
+                    ((packed >> 12) & 0xff) as u8,
+                    ((packed >> 22) & 0xff) as u8,
+                    (packed >> 30) as u8 * 85,
+                ]);
+            }
+        }
+        // Rgba8Unorm(Srgb): already in the encoding that is displayed.
+        _ => out.extend_from_slice(row),
+    }
+}
+
+fn unorm_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let encoded = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    unorm_to_u8(encoded)
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_half_floats() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0x3800), 0.5);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xfc00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn converts_rows_to_rgba8() {
+        let mut out = Vec::new();
+        convert_row(
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+            &mut out,
+        );
+        assert_eq!(out, [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        // Linear 1.0, 0.5, 0.0 and alpha 0.5, encoded to sRGB except for alpha.
+        let mut out = Vec::new();
+        let texel = [0x3c00u16, 0x3800, 0x0000, 0x3800]
+            .iter()
+            .flat_map(|bits| bits.to_le_bytes())
+            .collect::<Vec<_>>();
+        convert_row(wgpu::TextureFormat::Rgba16Float, &texel, &mut out);
+        assert_eq!(out, [255, 188, 0, 128]);
+
+        // 10-bit channels keep their 8 most significant bits, 2-bit alpha is scaled.
+        let mut out = Vec::new();
+        let packed: u32 = 0x3ff | (0x200 << 10) | (0x004 << 20) | (2 << 30);
+        convert_row(
+            wgpu::TextureFormat::Rgb10a2Unorm,
+            &packed.to_le_bytes(),
+            &mut out,
+        );
+        assert_eq!(out, [255, 128, 1, 170]);
+
+        let mut out = Vec::new();
+        convert_row(wgpu::TextureFormat::Rgba8Unorm, &[9, 8, 7, 6], &mut out);
+        assert_eq!(out, [9, 8, 7, 6]);
+    }
+}
@@ -0,0 +1,89 @@
+/// Errors that can occur while creating or using a graphics [`Context`](crate::Context).
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("no suitable graphics adapter found: {0}")]
+    NoAdapter(#[from] wgpu::RequestAdapterError),
+
+    #[error("no adapter matches {selector:?} among {} available adapters", available.len())]
+    NoMatchingAdapter {
+        selector: crate::AdapterSelector,
+        available: Vec<wgpu::AdapterInfo>,
+    },
+
+    #[error(
+        "failed to request device: {source} (missing features: {:?})",
+        requested_features.difference(*supported_features)
+    )]
+    RequestDevice {
+        source: wgpu::RequestDeviceError,
+        requested_features: wgpu::Features,
+        supported_features: wgpu::Features,
+        requested_limits: Box<wgpu::Limits>,
+        supported_limits: Box<wgpu::Limits>,
+    },
+
+    #[error("failed to create surface: {0}")]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+
+    #[error(
+        "surface supports none of the requested formats {requested:?} (supported: {supported:?})"
+    )]
+    UnsupportedSurfaceFormat {
+        requested: Vec<wgpu::TextureFormat>,
+        supported: Vec<wgpu::TextureFormat>,
+    },
+
+    #[error("frames in {0:?} cannot be captured")]
+    UnsupportedCaptureFormat(wgpu::TextureFormat),
+
+    #[error("failed to map readback buffer: {0}")]
+    BufferMap(#[from] wgpu::BufferAsyncError),
+
+    #[error("failed to poll device: {0}")]
+    Poll(#[from] wgpu::PollError),
+
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid texture: {0}")]
+    InvalidTexture(String),
+
+    #[error("cannot generate mipmaps for {0:?} texture with this dimension or usage")]
+    UnsupportedMipmapTexture(wgpu::TextureFormat),
+
+    #[error("pipeline does not match its shaders: {0}")]
+    InvalidPipeline(String),
+
+    #[error("GPU error: {0}")]
+    Gpu(#[from] wgpu::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Runs `f` inside validation, out-of-memory and internal error scopes and returns the
+/// first error it caused, instead of leaving it to the uncaptured error handler.
+pub(crate) fn capture_errors<T>(device: &wgpu::Device, f: impl FnOnce() -> T) -> Result<T> {
+    device.push_error_scope(wgpu::ErrorFilter::Internal);
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let value = f();
+
+    let mut error = None;
+    for _ in 0..3 {
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            error.get_or_insert(e);
+        }
+    }
+
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok(value),
+    }
+}
@@ -0,0 +1,23 @@
+/// Result of a call to [`Context::render`](crate::Context::render).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameOutcome {
+    /// The render closure ran and the frame was submitted and presented.
+    Presented,
+    /// The window has a zero size (usually minimized), so no frame was acquired.
+    SkippedMinimized,
+    /// Acquiring the frame kept timing out.
+    SkippedTimeout,
+    /// The surface was lost or outdated and could not be recovered this frame.
+    SkippedOutdated,
+    /// Acquiring the frame failed with another surface error.
+    SkippedError,
+    /// The device was lost; the context must be recreated before rendering again.
+    DeviceLost,
+}
+
+impl FrameOutcome {
+    /// Returns `true` if the render closure ran for this frame.
+    pub fn is_presented(self) -> bool {
+        self == FrameOutcome::Presented
+    }
+}
@@ -1,33 +1,96 @@
-use std::sync::Arc;
-use winit::window::Window;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use winit::window::{Window, WindowId};
 
+mod adapter;
+mod attachments;
+mod bind_group;
+mod blit;
+mod buffer;
+mod capture;
+mod error;
+mod frame;
+mod mipmap;
 mod pass;
 mod pipeline;
+mod pipeline_cache;
+mod profiler;
+mod reflect;
 mod shader;
+mod texture;
+mod uniform;
+mod window;
 
-pub use pass::RenderPassBuilder;
-pub use pipeline::RenderPipelineBuilder;
+pub use adapter::{AdapterSelector, enumerate_adapters};
+pub use bind_group::{BindGroupBuilder, BindGroupLayoutBuilder};
+pub use buffer::{Buffer, IndexType, RenderPassExt};
+pub use capture::Screenshot;
+pub use error::{Error, Result};
+pub use frame::FrameOutcome;
+pub use mipmap::MipmapGenerator;
+pub use pass::{ComputePassBuilder, ComputePassExt, RenderPassBuilder, workgroup_count};
+pub use pipeline::{ComputePipelineBuilder, RenderPipelineBuilder};
+pub use profiler::PassTiming;
+pub use reflect::{EntryPoint, ResourceBinding, ShaderReflection, VertexInput};
 pub use shader::Shader;
+pub use texture::{Texture, TextureBuilder};
+pub use uniform::Uniform;
+pub use window::WindowRequest;
+
+/// Number of times acquiring a frame is retried after a timeout before it is skipped.
+const MAX_ACQUIRE_TIMEOUT_RETRIES: u32 = 3;
 
 #[derive(Debug, Clone)]
 pub struct GraphicsBuilder {
     pub(crate) required_features: wgpu::Features,
-    pub(crate) required_limits: wgpu::Limits,
+    pub(crate) optional_features: wgpu::Features,
+    pub(crate) limits: LimitsPolicy,
     pub(crate) power_preference: wgpu::PowerPreference,
     pub(crate) backends: wgpu::Backends,
+    pub(crate) backend_fallbacks: Vec<wgpu::Backends>,
     pub(crate) present_mode: wgpu::PresentMode,
+    pub(crate) present_mode_fallbacks: Vec<wgpu::PresentMode>,
     pub(crate) force_fallback_adapter: bool,
+    pub(crate) headless_format: wgpu::TextureFormat,
+    pub(crate) adapter: Option<AdapterSelector>,
+    pub(crate) surface_formats: Vec<wgpu::TextureFormat>,
+    pub(crate) view_formats: Vec<wgpu::TextureFormat>,
+    pub(crate) alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+    pub(crate) msaa_samples: u32,
+    pub(crate) depth_format: Option<wgpu::TextureFormat>,
+    pub(crate) capture_surface: bool,
+    pub(crate) profiling: bool,
+    pub(crate) pipeline_cache_dir: Option<PathBuf>,
 }
 
 impl Default for GraphicsBuilder {
     fn default() -> Self {
         Self {
             required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::default(),
+            optional_features: wgpu::Features::empty(),
+            limits: LimitsPolicy::default(),
             power_preference: wgpu::PowerPreference::default(),
             backends: wgpu::Backends::DX12 | wgpu::Backends::VULKAN | wgpu::Backends::METAL,
+            backend_fallbacks: vec![wgpu::Backends::GL],
             present_mode: wgpu::PresentMode::Immediate,
+            present_mode_fallbacks: vec![
+                wgpu::PresentMode::Mailbox,
+                wgpu::PresentMode::Immediate,
+                wgpu::PresentMode::Fifo,
+            ],
             force_fallback_adapter: false,
+            headless_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            adapter: None,
+            surface_formats: Vec::new(),
+            view_formats: Vec::new(),
+            alpha_modes: Vec::new(),
+            msaa_samples: 1,
+            depth_format: None,
+            capture_surface: false,
+            profiling: false,
+            pipeline_cache_dir: None,
         }
     }
 }
@@ -47,8 +110,27 @@ impl GraphicsBuilder {
         self
     }
 
+    /// Sets features that are enabled only if the adapter supports them.
+    ///
+    /// Use [`Context::features`] to check which ones were granted.
+    pub fn optional_features(mut self, features: wgpu::Features) -> Self {
+        self.optional_features = features;
+        self
+    }
+
+    pub fn optional_feature(mut self, feature: wgpu::Features) -> Self {
+        self.optional_features |= feature;
+        self
+    }
+
+    /// Requires exactly these limits; shorthand for `limits_policy(LimitsPolicy::Exact(..))`.
     pub fn limits(mut self, limits: wgpu::Limits) -> Self {
-        self.required_limits = limits;
+        self.limits = LimitsPolicy::Exact(limits);
+        self
+    }
+
+    pub fn limits_policy(mut self, policy: LimitsPolicy) -> Self {
+        self.limits = policy;
         self
     }
 
@@ -62,90 +144,602 @@ impl GraphicsBuilder {
         self
     }
 
+    /// Sets the backend sets tried, in order, when no adapter or device can be created
+    /// with the primary backends. Defaults to `[Backends::GL]`.
+    pub fn backend_fallbacks(mut self, fallbacks: &[wgpu::Backends]) -> Self {
+        self.backend_fallbacks = fallbacks.to_vec();
+        self
+    }
+
     pub fn present_mode(mut self, mode: wgpu::PresentMode) -> Self {
         self.present_mode = mode;
         self
     }
 
+    /// Sets the present modes tried, in order, when the requested one is not supported
+    /// by the surface.
+    ///
+    /// `Fifo` is always used as a last resort since every surface supports it.
+    pub fn present_mode_fallbacks(mut self, modes: &[wgpu::PresentMode]) -> Self {
+        self.present_mode_fallbacks = modes.to_vec();
+        self
+    }
+
     pub fn force_fallback_adapter(mut self, force: bool) -> Self {
         self.force_fallback_adapter = force;
         self
     }
+
+    /// Picks the adapter explicitly instead of using the power preference.
+    ///
+    /// When set, `power_preference` and `force_fallback_adapter` are ignored and the first
+    /// adapter matching the selector (and compatible with the window surface) is used.
+    pub fn adapter(mut self, selector: AdapterSelector) -> Self {
+        self.adapter = Some(selector);
+        self
+    }
+
+    pub fn adapter_index(self, index: usize) -> Self {
+        self.adapter(AdapterSelector::Index(index))
+    }
+
+    pub fn adapter_name(self, name: impl Into<String>) -> Self {
+        self.adapter(AdapterSelector::Name(name.into()))
+    }
+
+    pub fn adapter_device_type(self, device_type: wgpu::DeviceType) -> Self {
+        self.adapter(AdapterSelector::DeviceType(device_type))
+    }
+
+    pub fn adapter_filter<F>(self, predicate: F) -> Self
+    where
+        F: Fn(&wgpu::AdapterInfo) -> bool + Send + Sync + 'static,
+    {
+        self.adapter(AdapterSelector::Predicate(Arc::new(predicate)))
+    }
+
+    /// Lists the adapters available on the configured backends.
+    pub fn enumerate_adapters(&self) -> Vec<wgpu::AdapterInfo> {
+        enumerate_adapters(self.backends)
+    }
+
+    /// Sets the surface formats to try, in priority order.
+    ///
+    /// The first format supported by the surface is used. When the list is empty (the
+    /// default), the first sRGB format reported by the surface is picked.
+    pub fn surface_formats(mut self, formats: &[wgpu::TextureFormat]) -> Self {
+        self.surface_formats = formats.to_vec();
+        self
+    }
+
+    /// Appends a format to the surface format preference list.
+    pub fn surface_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.surface_formats.push(format);
+        self
+    }
+
+    /// Sets additional formats that views of the frame texture may use.
+    ///
+    /// Only the sRGB or linear counterpart of the selected format is allowed; other formats
+    /// are ignored with a warning. Use [`Context::frame_view`] to create such a view.
+    pub fn view_formats(mut self, formats: &[wgpu::TextureFormat]) -> Self {
+        self.view_formats = formats.to_vec();
+        self
+    }
+
+    /// Sets the composite alpha modes to try, in priority order.
+    ///
+    /// The first mode supported by the surface is used. When the list is empty (the default)
+    /// or no mode is supported, `CompositeAlphaMode::Auto` is used.
+    pub fn alpha_modes(mut self, modes: &[wgpu::CompositeAlphaMode]) -> Self {
+        self.alpha_modes = modes.to_vec();
+        self
+    }
+
+    /// Appends a mode to the composite alpha mode preference list.
+    pub fn alpha_mode(mut self, mode: wgpu::CompositeAlphaMode) -> Self {
+        self.alpha_modes.push(mode);
+        self
+    }
+
+    /// Enables multisample anti-aliasing with the given sample count.
+    ///
+    /// The [`Context`] then owns a multisampled color target matching the surface, which
+    /// [`Context::render_pass`] renders into and resolves to the frame, and
+    /// [`Context::render_pipeline`] uses the same sample count. If the surface format does
+    /// not support `samples`, the highest supported lower count is used.
+    pub fn msaa(mut self, samples: u32) -> Self {
+        self.msaa_samples = samples;
+        self
+    }
+
+    /// Makes the [`Context`] own a depth(-stencil) buffer of this format, sized to the
+    /// surface and recreated on resize.
+    ///
+    /// Use [`RenderPipelineBuilder::with_depth`] and [`RenderPassBuilder::with_depth`] to
+    /// use it. Formats needing a missing device feature fall back to `Depth24PlusStencil8`.
+    pub fn depth_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.depth_format = Some(format);
+        self
+    }
+
+    /// Configures window surfaces with `COPY_SRC` usage when the surface supports it, so
+    /// captured frames are read back directly instead of through an intermediate texture.
+    pub fn capture_surface(mut self, enabled: bool) -> Self {
+        self.capture_surface = enabled;
+        self
+    }
+
+    /// Times the passes built through the [`Context`] with GPU timestamp queries.
+    ///
+    /// Requests `TIMESTAMP_QUERY` as an optional feature. Profiling is a no-op on adapters
+    /// without it. See [`Context::pass_timings`].
+    pub fn profiling(mut self, enabled: bool) -> Self {
+        self.profiling = enabled;
+        self
+    }
+
+    /// Persists compiled pipelines in `dir` so later runs can skip recompiling them.
+    ///
+    /// Pipelines built with [`Context::render_pipeline`] use the cache, which is written
+    /// back with [`Context::save_pipeline_cache`]. Requests `PIPELINE_CACHE` as an optional
+    /// feature; caching is skipped on backends that do not support it.
+    pub fn pipeline_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.pipeline_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the color format of the offscreen target used by headless contexts.
+    pub fn headless_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.headless_format = format;
+        self
+    }
+
+    /// Applies the standard wgpu environment variables on top of this configuration.
+    ///
+    /// `WGPU_BACKEND` replaces the backends and disables the fallback chain,
+    /// `WGPU_POWER_PREF` overrides the power preference and `WGPU_ADAPTER_NAME` selects
+    /// the adapter by name.
+    pub(crate) fn with_env(&self) -> Self {
+        let mut graphics = self.clone();
+
+        if let Some(backends) = wgpu::Backends::from_env() {
+            graphics.backends = backends;
+            graphics.backend_fallbacks.clear();
+        }
+
+        if let Some(preference) = wgpu::PowerPreference::from_env() {
+            graphics.power_preference = preference;
+        }
+
+        if let Ok(name) = std::env::var("WGPU_ADAPTER_NAME") {
+            graphics.adapter = Some(AdapterSelector::Name(name));
+        }
+
+        graphics
+    }
+
+    /// Creates a headless [`Context`] rendering into an offscreen texture of the given size.
+    ///
+    /// This blocks on the asynchronous device creation; use [`Context::new_headless`]
+    /// from async code instead.
+    pub fn build_headless(&self, width: u32, height: u32) -> Result<Context> {
+        pollster::block_on(Context::new_headless(width, height, self))
+    }
+}
+
+/// How the limits requested from the device are chosen.
+#[derive(Debug, Clone)]
+pub enum LimitsPolicy {
+    /// Requires exactly these limits, failing if the adapter cannot provide them.
+    Exact(wgpu::Limits),
+    /// Requests the best limits supported by the adapter.
+    AdapterMaximum,
+    /// Requests [`wgpu::Limits::downlevel_defaults`], with the texture size limits raised
+    /// to what the adapter supports.
+    DownlevelDefaults,
+    /// Requests [`wgpu::Limits::downlevel_webgl2_defaults`], with the texture size limits
+    /// raised to what the adapter supports.
+    DownlevelWebgl2Defaults,
+}
+
+impl Default for LimitsPolicy {
+    fn default() -> Self {
+        LimitsPolicy::Exact(wgpu::Limits::default())
+    }
+}
+
+impl LimitsPolicy {
+    /// Returns the limits to request from an adapter supporting `supported`.
+    pub fn resolve(&self, supported: &wgpu::Limits) -> wgpu::Limits {
+        match self {
+            LimitsPolicy::Exact(limits) => limits.clone(),
+            LimitsPolicy::AdapterMaximum => supported.clone(),
+            LimitsPolicy::DownlevelDefaults => {
+                wgpu::Limits::downlevel_defaults().using_resolution(supported.clone())
+            }
+            LimitsPolicy::DownlevelWebgl2Defaults => {
+                wgpu::Limits::downlevel_webgl2_defaults().using_resolution(supported.clone())
+            }
+        }
+    }
 }
 
 pub struct Context {
-    pub surface: wgpu::Surface<'static>,
+    pub surface: Option<wgpu::Surface<'static>>,
+    pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
-    pub window: Arc<Window>,
+    pub window: Option<Arc<Window>>,
+    /// Offscreen color target rendered into by headless contexts.
+    pub target: Option<wgpu::Texture>,
+    instance: wgpu::Instance,
+    msaa_view: Option<wgpu::TextureView>,
+    sample_count: u32,
+    depth_view: Option<wgpu::TextureView>,
+    depth_format: Option<wgpu::TextureFormat>,
+    current_frame: Option<wgpu::Texture>,
+    graphics: GraphicsBuilder,
+    device_lost: Arc<AtomicBool>,
+    window_requests: Arc<Mutex<Vec<WindowRequest>>>,
+    capture: Option<capture::CaptureRequest>,
+    last_capture: Option<Screenshot>,
+    capture_target: Option<wgpu::Texture>,
+    recording: bool,
+    pending_readbacks: VecDeque<capture::Readback>,
+    recorded_frames: Vec<Screenshot>,
+    blitter: Option<blit::Blitter>,
+    mipmap_generator: MipmapGenerator,
+    profiler: Option<profiler::Profiler>,
+    pipeline_cache: Option<pipeline_cache::PipelineCache>,
+}
+
+/// The device a context renders with, and the state shared by every context created
+/// from it with [`Context::with_shared_device`].
+struct DeviceParts {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    device_lost: Arc<AtomicBool>,
+    window_requests: Arc<Mutex<Vec<WindowRequest>>>,
+    pipeline_cache: Option<pipeline_cache::PipelineCache>,
+}
+
+impl DeviceParts {
+    /// Requests an adapter and device from `instance` and sets up device loss tracking,
+    /// error reporting and the pipeline cache.
+    async fn request(
+        instance: wgpu::Instance,
+        backends: wgpu::Backends,
+        graphics: &GraphicsBuilder,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<Self> {
+        let (adapter, device, queue) =
+            request_device(&instance, backends, graphics, compatible_surface).await?;
+        let device_lost = watch_device_lost(&device);
+        report_uncaptured_errors(&device, &adapter);
+        let pipeline_cache = graphics
+            .pipeline_cache_dir
+            .as_deref()
+            .and_then(|dir| pipeline_cache::PipelineCache::load(&device, &adapter, dir));
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            device_lost,
+            window_requests: Arc::default(),
+            pipeline_cache,
+        })
+    }
 }
 
 impl Context {
-    pub async fn new(window: Arc<Window>, graphics: &GraphicsBuilder) -> Self {
+    /// Creates a context rendering to the given window.
+    ///
+    /// The backends of the [`GraphicsBuilder`] are tried first, then each of its backend
+    /// fallbacks. The `WGPU_BACKEND`, `WGPU_POWER_PREF` and `WGPU_ADAPTER_NAME` environment
+    /// variables take precedence over the builder.
+    ///
+    /// Returns the last [`Error`] if no compatible adapter is found, the device cannot be
+    /// created with the requested features and limits, or the surface cannot be configured.
+    pub async fn new(window: Arc<Window>, graphics: &GraphicsBuilder) -> Result<Self> {
+        let graphics = graphics.with_env();
+        try_backends(&graphics, |backends| {
+            Self::with_backends(window.clone(), &graphics, backends)
+        })
+        .await
+    }
+
+    async fn with_backends(
+        window: Arc<Window>,
+        graphics: &GraphicsBuilder,
+        backends: wgpu::Backends,
+    ) -> Result<Self> {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: graphics.backends,
+            backends,
             ..Default::default()
         });
 
-        let surface = instance.create_surface(window.clone()).unwrap();
+        let surface = instance.create_surface(window.clone())?;
+        let parts = DeviceParts::request(instance, backends, graphics, Some(&surface)).await?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: graphics.power_preference,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: graphics.force_fallback_adapter,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: Some("Soyuz Device"),
-                required_features: graphics.required_features,
-                required_limits: graphics.required_limits.clone(),
-                experimental_features: wgpu::ExperimentalFeatures::default(),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::default(),
-            })
-            .await
-            .unwrap_or_else(|e| panic!("Failed to request device: {:?}", e));
+        let config = surface_config(graphics, &surface.get_capabilities(&parts.adapter), size)?;
+        surface.configure(&parts.device, &config);
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        tracing::info!("Soyuz graphics context initialized");
+        log_adapter_info(&parts.adapter);
+
+        Ok(Self::from_parts(
+            parts,
+            Some(surface),
+            Some(window),
+            None,
+            config,
+            size,
+            graphics,
+        ))
+    }
+
+    /// Creates a context without a window, rendering into an owned offscreen texture.
+    ///
+    /// The texture uses [`GraphicsBuilder::headless_format`] and is created with
+    /// `RENDER_ATTACHMENT | COPY_SRC | TEXTURE_BINDING` usage so it can be read back
+    /// or sampled after rendering.
+    pub async fn new_headless(width: u32, height: u32, graphics: &GraphicsBuilder) -> Result<Self> {
+        let graphics = graphics.with_env();
+        try_backends(&graphics, |backends| {
+            Self::headless_with_backends(width, height, &graphics, backends)
+        })
+        .await
+    }
+
+    async fn headless_with_backends(
+        width: u32,
+        height: u32,
+        graphics: &GraphicsBuilder,
+        backends: wgpu::Backends,
+    ) -> Result<Self> {
+        let size = winit::dpi::PhysicalSize::new(width.max(1), height.max(1));
+
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+
+        let parts = DeviceParts::request(instance, backends, graphics, None).await?;
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            format: graphics.headless_format,
             width: size.width,
             height: size.height,
             present_mode: graphics.present_mode,
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
+            view_formats: compatible_view_formats(graphics.headless_format, &graphics.view_formats),
             desired_maximum_frame_latency: 2,
         };
 
-        surface.configure(&device, &config);
+        let target = create_offscreen_target(&parts.device, &config);
 
-        tracing::info!("Soyuz graphics context initialized");
-        tracing::info!("GPU: {}", adapter.get_info().name);
+        tracing::info!("Soyuz headless graphics context initialized");
+        log_adapter_info(&parts.adapter);
 
-        Self {
+        Ok(Self::from_parts(
+            parts,
+            None,
+            None,
+            Some(target),
+            config,
+            size,
+            graphics,
+        ))
+    }
+
+    /// Creates a context for another window, sharing this context's adapter, device and
+    /// queue.
+    ///
+    /// Resources created from either context can be used with the other. The new surface
+    /// is configured from the same [`GraphicsBuilder`] as this context.
+    pub fn with_shared_device(&self, window: Arc<Window>) -> Result<Self> {
+        let size = window.inner_size();
+
+        let surface = self.instance.create_surface(window.clone())?;
+        let config = surface_config(
+            &self.graphics,
+            &surface.get_capabilities(&self.adapter),
+            size,
+        )?;
+        surface.configure(&self.device, &config);
+
+        tracing::info!("Window surface created for {:?}", window.id());
+
+        let parts = DeviceParts {
+            instance: self.instance.clone(),
+            adapter: self.adapter.clone(),
+            device: self.device.clone(),
+            queue: self.queue.clone(),
+            device_lost: self.device_lost.clone(),
+            window_requests: self.window_requests.clone(),
+            pipeline_cache: self.pipeline_cache.clone(),
+        };
+
+        Ok(Self::from_parts(
+            parts,
+            Some(surface),
+            Some(window),
+            None,
+            config,
+            size,
+            &self.graphics,
+        ))
+    }
+
+    /// Creates a context rendering to `surface` or `target` with the device in `parts`,
+    /// along with its MSAA and depth attachments and its profiler.
+    fn from_parts(
+        parts: DeviceParts,
+        surface: Option<wgpu::Surface<'static>>,
+        window: Option<Arc<Window>>,
+        target: Option<wgpu::Texture>,
+        config: wgpu::SurfaceConfiguration,
+        size: winit::dpi::PhysicalSize<u32>,
+        graphics: &GraphicsBuilder,
+    ) -> Self {
+        let DeviceParts {
+            instance,
+            adapter,
+            device,
+            queue,
+            device_lost,
+            window_requests,
+            pipeline_cache,
+        } = parts;
+
+        let sample_count = attachments::select_sample_count(
+            graphics.msaa_samples,
+            &adapter,
+            &device,
+            config.format,
+        );
+        let depth_format = attachments::select_depth_format(graphics.depth_format, &device);
+        let profiler = graphics
+            .profiling
+            .then(|| profiler::Profiler::new(&device, &queue))
+            .flatten();
+
+        let mut context = Self {
             surface,
+            adapter,
             device,
             queue,
             config,
             size,
             window,
+            target,
+            instance,
+            msaa_view: None,
+            sample_count,
+            depth_view: None,
+            depth_format,
+            current_frame: None,
+            graphics: graphics.clone(),
+            device_lost,
+            window_requests,
+            capture: None,
+            last_capture: None,
+            capture_target: None,
+            recording: false,
+            pending_readbacks: VecDeque::new(),
+            recorded_frames: Vec::new(),
+            blitter: None,
+            mipmap_generator: MipmapGenerator::new(),
+            profiler,
+            pipeline_cache,
+        };
+        context.recreate_attachments();
+
+        context
+    }
+
+    /// Returns the id of the window this context renders to, or `None` when headless.
+    pub fn window_id(&self) -> Option<WindowId> {
+        self.window.as_ref().map(|window| window.id())
+    }
+
+    /// Requests a new window sharing this context's device.
+    ///
+    /// The window is created by the event loop owner once control returns to it; with
+    /// `soyuz-app`, the new window's context is then passed to `App::window_opened`.
+    pub fn open_window(&self, attributes: winit::window::WindowAttributes) {
+        self.push_window_request(WindowRequest::Open(Box::new(attributes)));
+    }
+
+    /// Requests the window with the given id to be closed.
+    pub fn close_window(&self, id: WindowId) {
+        self.push_window_request(WindowRequest::Close(id));
+    }
+
+    /// Takes the pending window requests made by this context and every context sharing
+    /// its device.
+    pub fn take_window_requests(&self) -> Vec<WindowRequest> {
+        match self.window_requests.lock() {
+            Ok(mut requests) => std::mem::take(&mut *requests),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn push_window_request(&self, request: WindowRequest) {
+        if let Ok(mut requests) = self.window_requests.lock() {
+            requests.push(request);
+        }
+    }
+
+    /// Returns information about the adapter the device was created on.
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Returns `true` once the device has been lost and the context must be recreated
+    /// with [`Context::recreate`].
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    /// Recreates the adapter, device, queue and surface (or offscreen target) from the
+    /// original [`GraphicsBuilder`], keeping the current size and present mode.
+    ///
+    /// Every resource created from the previous device (buffers, textures, pipelines, ...)
+    /// becomes invalid and must be rebuilt. Contexts created with
+    /// [`Context::with_shared_device`] keep the old device and must be recreated from
+    /// this one.
+    pub async fn recreate(&mut self) -> Result<()> {
+        tracing::warn!("Recreating graphics context...");
+
+        let present_mode = self.config.present_mode;
+
+        // The new device starts from the saved cache, so keep what the old one gathered.
+        self.save_pipeline_cache();
+
+        // The old surface must be released before a new one can be created for the window.
+        self.surface = None;
+
+        let mut context = match self.window.clone() {
+            Some(window) => Context::new(window, &self.graphics).await?,
+            None => {
+                Context::new_headless(self.size.width, self.size.height, &self.graphics).await?
+            }
+        };
+
+        if context.config.present_mode != present_mode {
+            context.set_present_mode(present_mode);
         }
+        context.window_requests = self.window_requests.clone();
+
+        *self = context;
+        Ok(())
+    }
+
+    /// Returns the features enabled on the device, including granted optional features.
+    pub fn features(&self) -> wgpu::Features {
+        self.device.features()
+    }
+
+    /// Returns the limits the device was created with.
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -153,35 +747,50 @@ impl Context {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            if let Some(surface) = self.surface.as_ref() {
+                surface.configure(&self.device, &self.config);
+            }
+            if self.target.is_some() {
+                self.target = Some(create_offscreen_target(&self.device, &self.config));
+            }
+            self.recreate_attachments();
             tracing::debug!("Surface resized to {}x{}", new_size.width, new_size.height);
         }
     }
 
-    pub fn render<F>(&mut self, render_fn: F)
+    /// Acquires the next frame, runs `render_fn` on it, then submits and presents it.
+    ///
+    /// Outdated or lost surfaces are reconfigured with the current window size and
+    /// acquired again, and timeouts are retried a few times. Returns a [`FrameOutcome`]
+    /// telling whether the closure actually ran.
+    pub fn render<F>(&mut self, render_fn: F) -> FrameOutcome
     where
         F: FnOnce(&mut Context, &wgpu::TextureView, &mut wgpu::CommandEncoder),
     {
-        let output = match self.surface.get_current_texture() {
-            Ok(texture) => texture,
-            Err(wgpu::SurfaceError::Lost) => {
-                tracing::warn!("Surface lost, reconfiguring...");
-                self.surface.configure(&self.device, &self.config);
-                return;
-            }
-            Err(wgpu::SurfaceError::OutOfMemory) => {
-                tracing::error!("Out of memory!");
-                panic!("Out of memory!");
-            }
-            Err(e) => {
-                tracing::error!("Surface error: {:?}", e);
-                return;
+        if self.is_device_lost() {
+            return FrameOutcome::DeviceLost;
+        }
+
+        let (output, texture) = if let Some(target) = self.target.as_ref() {
+            (None, target.clone())
+        } else {
+            match self.acquire_frame() {
+                Ok(output) => {
+                    let texture = output.texture.clone();
+                    (Some(output), texture)
+                }
+                Err(outcome) => return outcome,
             }
         };
 
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        // Frames that cannot be copied from are rendered into an intermediate texture,
+        // which is read back and blitted to the frame.
+        let capture = self.capture.take();
+        let intermediate = ((capture.is_some() || self.recording)
+            && !texture.usage().contains(wgpu::TextureUsages::COPY_SRC))
+        .then(|| self.capture_target());
+        let frame = intermediate.clone().unwrap_or_else(|| texture.clone());
+        let view = frame.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self
             .device
@@ -189,32 +798,547 @@ impl Context {
                 label: Some("Render Encoder"),
             });
 
+        self.current_frame = Some(frame.clone());
         render_fn(self, &view, &mut encoder);
+        self.current_frame = None;
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if intermediate.is_some() {
+            let target = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let format = self.config.format;
+            self.blitter
+                .get_or_insert_with(|| blit::Blitter::new(&self.device))
+                .blit(&self.device, &mut encoder, &view, &target, format);
+        }
+
+        let readback = capture.as_ref().and_then(|_| {
+            capture::Readback::new(&self.device, &mut encoder, &frame)
+                .inspect_err(|e| tracing::error!("Failed to capture frame: {}", e))
+                .ok()
+        });
+        let recorded = self
+            .recording
+            .then(|| {
+                capture::Readback::new(&self.device, &mut encoder, &frame)
+                    .inspect_err(|e| tracing::error!("Failed to record frame: {}", e))
+                    .ok()
+            })
+            .flatten();
+
+        self.submit(encoder);
+        if let Some(output) = output {
+            let suboptimal = output.suboptimal;
+            output.present();
+            if suboptimal {
+                tracing::debug!("Surface is suboptimal, reconfiguring...");
+                self.reconfigure_surface();
+            }
+        }
+
+        if let (Some(request), Some(readback)) = (capture, readback) {
+            self.finish_capture(request, readback);
+        }
+
+        if let Some(mut readback) = recorded {
+            readback.map();
+            self.pending_readbacks.push_back(readback);
+            self.collect_recorded_frames(false);
+        }
+
+        FrameOutcome::Presented
+    }
+
+    /// Captures the next frame rendered with [`Context::render`].
+    ///
+    /// The screenshot can be taken with [`Context::take_capture`] once the frame has been
+    /// rendered.
+    pub fn request_capture(&mut self) {
+        self.capture = Some(capture::CaptureRequest::Keep);
+    }
+
+    /// Captures the next frame rendered with [`Context::render`] and saves it as a PNG
+    /// file at `path`. Failures are logged.
+    pub fn save_screenshot(&mut self, path: impl Into<PathBuf>) {
+        self.capture = Some(capture::CaptureRequest::Save(path.into()));
+    }
+
+    /// Returns the last frame captured after [`Context::request_capture`], if any.
+    pub fn take_capture(&mut self) -> Option<Screenshot> {
+        self.last_capture.take()
+    }
+
+    /// Starts or stops capturing every frame rendered with [`Context::render`].
+    ///
+    /// Frames are read back asynchronously, so they become available from
+    /// [`Context::take_recorded_frames`] a few frames after being rendered. Rendering only
+    /// waits for the GPU when too many readbacks are in flight. Stopping the recording
+    /// waits for the frames still in flight.
+    pub fn set_recording(&mut self, recording: bool) {
+        if self.recording && !recording {
+            self.flush_recorded_frames();
+        }
+        self.recording = recording;
+    }
+
+    /// Returns whether every rendered frame is being captured.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Returns the recorded frames whose readback has completed, in render order.
+    pub fn take_recorded_frames(&mut self) -> Vec<Screenshot> {
+        self.collect_recorded_frames(false);
+        std::mem::take(&mut self.recorded_frames)
+    }
+
+    /// Waits for every recorded frame still in flight to be read back.
+    pub fn flush_recorded_frames(&mut self) {
+        self.collect_recorded_frames(true);
+    }
+
+    /// Moves completed readbacks to the recorded frames. Waits for the oldest ones while
+    /// more than [`capture::MAX_PENDING_READBACKS`] are pending, or for all of them when
+    /// `wait` is set.
+    fn collect_recorded_frames(&mut self, wait: bool) {
+        if self.pending_readbacks.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.device.poll(wgpu::PollType::Poll) {
+            tracing::error!("Failed to poll device: {}", e);
+        }
+
+        loop {
+            let must_wait = wait || self.pending_readbacks.len() > capture::MAX_PENDING_READBACKS;
+            let Some(readback) = self.pending_readbacks.front_mut() else {
+                break;
+            };
+            let result = match readback.try_finish() {
+                Some(result) => result,
+                None if must_wait => readback.wait(&self.device),
+                None => break,
+            };
+            self.pending_readbacks.pop_front();
+
+            match result {
+                Ok(screenshot) => self.recorded_frames.push(screenshot),
+                Err(e) => tracing::error!("Failed to read back recorded frame: {}", e),
+            }
+        }
+    }
+
+    /// Returns the intermediate texture frames are rendered into when they are captured
+    /// but the frame itself cannot be copied from.
+    fn capture_target(&mut self) -> wgpu::Texture {
+        let config = &self.config;
+        if let Some(texture) = self.capture_target.as_ref().filter(|texture| {
+            texture.width() == config.width
+                && texture.height() == config.height
+                && texture.format() == config.format
+        }) {
+            return texture.clone();
+        }
+
+        let texture = create_offscreen_target(
+            &self.device,
+            &wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                ..self.config.clone()
+            },
+        );
+        self.capture_target = Some(texture.clone());
+        texture
+    }
+
+    fn finish_capture(
+        &mut self,
+        request: capture::CaptureRequest,
+        mut readback: capture::Readback,
+    ) {
+        let screenshot = match readback.wait(&self.device) {
+            Ok(screenshot) => screenshot,
+            Err(e) => {
+                tracing::error!("Failed to read back captured frame: {}", e);
+                return;
+            }
+        };
+
+        match request {
+            capture::CaptureRequest::Keep => self.last_capture = Some(screenshot),
+            capture::CaptureRequest::Save(path) => match screenshot.save_png(&path) {
+                Ok(()) => tracing::info!("Saved screenshot to {}", path.display()),
+                Err(e) => tracing::error!("Failed to save screenshot to {}: {}", path.display(), e),
+            },
+        }
+    }
+
+    fn acquire_frame(&mut self) -> Result<wgpu::SurfaceTexture, FrameOutcome> {
+        if self.window.as_ref().is_some_and(|window| {
+            let size = window.inner_size();
+            size.width == 0 || size.height == 0 || window.is_minimized() == Some(true)
+        }) {
+            return Err(FrameOutcome::SkippedMinimized);
+        }
+
+        let mut timeouts = 0;
+        let mut reconfigured = false;
+
+        loop {
+            let Some(surface) = self.surface.as_ref() else {
+                return Err(FrameOutcome::SkippedError);
+            };
+
+            match surface.get_current_texture() {
+                Ok(output) => return Ok(output),
+                Err(e @ (wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost)) => {
+                    if reconfigured {
+                        tracing::warn!("Surface still {:?} after reconfiguring, skipping frame", e);
+                        return Err(FrameOutcome::SkippedOutdated);
+                    }
+                    tracing::debug!("Surface {:?}, reconfiguring...", e);
+                    self.reconfigure_surface();
+                    reconfigured = true;
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
+                    timeouts += 1;
+                    if timeouts > MAX_ACQUIRE_TIMEOUT_RETRIES {
+                        tracing::warn!("Timed out acquiring the surface texture, skipping frame");
+                        return Err(FrameOutcome::SkippedTimeout);
+                    }
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    tracing::error!("Out of memory, the device must be recreated");
+                    self.device_lost.store(true, Ordering::Release);
+                    return Err(FrameOutcome::DeviceLost);
+                }
+                Err(e) => {
+                    tracing::error!("Surface error: {:?}", e);
+                    return Err(FrameOutcome::SkippedError);
+                }
+            }
+        }
+    }
+
+    fn reconfigure_surface(&mut self) {
+        if let Some(window) = self.window.as_ref() {
+            let size = window.inner_size();
+            if size.width > 0 && size.height > 0 && size != self.size {
+                self.size = size;
+                self.config.width = size.width;
+                self.config.height = size.height;
+                self.recreate_attachments();
+            }
+        }
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
     }
 
+    /// Recreates the render attachments owned by the context to match the current size.
+    fn recreate_attachments(&mut self) {
+        self.msaa_view = (self.sample_count > 1)
+            .then(|| attachments::create_msaa_view(&self.device, &self.config, self.sample_count));
+        self.depth_view = self.depth_format.map(|format| {
+            attachments::create_depth_view(&self.device, &self.config, format, self.sample_count)
+        });
+    }
+
+    /// Returns the sample count of the managed MSAA target, or 1 when MSAA is disabled.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Returns the format of the managed depth buffer, if any.
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    /// Returns the view of the managed depth buffer, if a depth format was set.
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth_view.as_ref()
+    }
+
+    /// Returns the view of the managed multisampled color target, if MSAA is enabled.
+    pub fn msaa_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa_view.as_ref()
+    }
+
+    /// Creates a view of the frame being rendered with another format.
+    ///
+    /// The format must be the configured format or one of the view formats set with
+    /// [`GraphicsBuilder::view_formats`]. Returns `None` outside of [`Context::render`].
+    pub fn frame_view(&self, format: wgpu::TextureFormat) -> Option<wgpu::TextureView> {
+        self.current_frame.as_ref().map(|texture| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                format: Some(format),
+                ..Default::default()
+            })
+        })
+    }
+
+    /// Starts building a render pass targeting `view`.
+    ///
+    /// When MSAA is enabled, the pass renders into the managed multisampled target and
+    /// resolves into `view`.
     pub fn render_pass<'a>(
-        &self,
+        &'a self,
         encoder: &'a mut wgpu::CommandEncoder,
         view: &'a wgpu::TextureView,
     ) -> RenderPassBuilder<'a> {
-        RenderPassBuilder::new(encoder, view)
+        let builder = match self.msaa_view.as_ref() {
+            Some(msaa_view) => RenderPassBuilder::new(encoder, msaa_view).resolve_target(view),
+            None => RenderPassBuilder::new(encoder, view),
+        };
+        let builder = match self.profiler.as_ref() {
+            Some(profiler) => builder.profiler(profiler),
+            None => builder,
+        };
+
+        match (self.depth_view.as_ref(), self.depth_format) {
+            (Some(depth_view), Some(format)) => builder.depth_target(depth_view, format),
+            _ => builder,
+        }
+    }
+
+    /// Creates a compute pass builder for `encoder`, timed by the profiler when profiling
+    /// is enabled. Submit `encoder` with [`Context::submit`] or record the pass in
+    /// [`Context::render`] for its timestamps to be resolved.
+    pub fn compute_pass<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+    ) -> ComputePassBuilder<'a> {
+        let builder = ComputePassBuilder::new(encoder);
+        match self.profiler.as_ref() {
+            Some(profiler) => builder.profiler(profiler),
+            None => builder,
+        }
+    }
+
+    /// Submits `encoder`, first recording the resolve of the timestamps of every pass timed
+    /// since the last submission into it.
+    ///
+    /// Use this instead of `queue.submit` for encoders holding passes from
+    /// [`Context::compute_pass`] or [`Context::render_pass`] recorded outside of
+    /// [`Context::render`], which submits its own encoder the same way.
+    pub fn submit(&self, mut encoder: wgpu::CommandEncoder) {
+        if let Some(profiler) = self.profiler.as_ref() {
+            profiler.end_frame(&self.device, &mut encoder);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(profiler) = self.profiler.as_ref() {
+            profiler.after_submit(&self.device);
+        }
+    }
+
+    /// Returns whether passes are being timed with GPU timestamp queries.
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Returns the GPU time of each pass timed in the most recent frame whose timestamps
+    /// have been read back, which lags a few frames behind rendering.
+    ///
+    /// Passes from [`Context::render_pass`] are timed under their label. Empty when
+    /// [`GraphicsBuilder::profiling`] is disabled or unsupported.
+    pub fn pass_timings(&self) -> Vec<PassTiming> {
+        self.profiler
+            .as_ref()
+            .map(profiler::Profiler::timings)
+            .unwrap_or_default()
+    }
+
+    /// Returns timestamp writes timing a render pass named `label`, for passes not built
+    /// with [`Context::render_pass`]. `None` when profiling is disabled.
+    ///
+    /// Timestamps are resolved by the next [`Context::submit`] or [`Context::render`].
+    pub fn render_timestamp_writes(
+        &self,
+        label: &str,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        self.profiler
+            .as_ref()
+            .and_then(|profiler| profiler.render_timestamp_writes(label))
+    }
+
+    /// Returns timestamp writes timing a compute pass named `label`. `None` when profiling
+    /// is disabled.
+    ///
+    /// Timestamps are resolved by the next [`Context::submit`] or [`Context::render`].
+    pub fn compute_timestamp_writes(
+        &self,
+        label: &str,
+    ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        self.profiler
+            .as_ref()
+            .and_then(|profiler| profiler.compute_timestamp_writes(label))
+    }
+
+    /// Creates a vertex buffer initialized with `data`.
+    pub fn vertex_buffer<T: bytemuck::Pod>(&self, data: &[T]) -> Buffer<T> {
+        Buffer::new(
+            &self.device,
+            Some("Vertex Buffer"),
+            data,
+            wgpu::BufferUsages::VERTEX,
+        )
+    }
+
+    /// Creates an index buffer initialized with `indices`.
+    pub fn index_buffer<T: IndexType>(&self, indices: &[T]) -> Buffer<T> {
+        Buffer::new(
+            &self.device,
+            Some("Index Buffer"),
+            indices,
+            wgpu::BufferUsages::INDEX,
+        )
+    }
+
+    /// Creates a uniform buffer holding `value`.
+    pub fn uniform_buffer<T: bytemuck::Pod>(&self, value: &T) -> Buffer<T> {
+        Buffer::new(
+            &self.device,
+            Some("Uniform Buffer"),
+            std::slice::from_ref(value),
+            wgpu::BufferUsages::UNIFORM,
+        )
+    }
+
+    /// Creates a storage buffer initialized with `data`, which can also be copied from.
+    pub fn storage_buffer<T: bytemuck::Pod>(&self, data: &[T]) -> Buffer<T> {
+        Buffer::new(
+            &self.device,
+            Some("Storage Buffer"),
+            data,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        )
+    }
+
+    /// Creates a [`Uniform`] holding `value`, bound at `binding` for the given stages.
+    pub fn uniform<T: bytemuck::Pod>(
+        &self,
+        value: &T,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> Uniform<T> {
+        Uniform::new(&self.device, value, binding, visibility)
+    }
+
+    /// Starts building a [`Texture`] from an image file or RGBA pixels.
+    pub fn texture(&self) -> TextureBuilder<'_> {
+        TextureBuilder::new(&self.device, &self.queue).mipmap_generator(self.mipmap_generator())
+    }
+
+    /// Returns the mipmap generator used by [`Context::generate_mipmaps`] and textures
+    /// created with [`Context::texture`].
+    pub fn mipmap_generator(&self) -> &MipmapGenerator {
+        &self.mipmap_generator
+    }
+
+    /// Fills every mip level of `texture` from its base level and submits the work.
+    /// See [`MipmapGenerator::generate`] for the usages the texture needs.
+    pub fn generate_mipmaps(&self, texture: &wgpu::Texture) -> Result<()> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Mipmap Encoder"),
+            });
+        self.mipmap_generator()
+            .generate(&self.device, &mut encoder, texture)?;
+        self.queue.submit(Some(encoder.finish()));
+        Ok(())
     }
 
     pub fn shader(&self, source: &str) -> Shader {
         Shader::from_wgsl(&self.device, source)
     }
 
+    /// Compiles WGSL source, returning compilation errors. See [`Shader::try_from_wgsl`].
+    pub fn try_shader(&self, source: &str) -> Result<Shader> {
+        Shader::try_from_wgsl(&self.device, source)
+    }
+
+    /// Runs `f`, returning the first GPU error it causes instead of logging it.
+    ///
+    /// GPU errors raised outside of a scope, such as validation errors in
+    /// [`Context::render`], are logged with `tracing` rather than panicking.
+    pub fn error_scope<T>(&mut self, f: impl FnOnce(&mut Context) -> T) -> Result<T> {
+        let device = self.device.clone();
+        error::capture_errors(&device, || f(self))
+    }
+
+    pub fn bind_group_layout(&self) -> BindGroupLayoutBuilder<'_> {
+        BindGroupLayoutBuilder::new(&self.device)
+    }
+
+    pub fn bind_group<'a>(&'a self, layout: &'a wgpu::BindGroupLayout) -> BindGroupBuilder<'a> {
+        BindGroupBuilder::new(&self.device, layout)
+    }
+
     pub fn render_pipeline(&self) -> RenderPipelineBuilder<'_> {
-        RenderPipelineBuilder::new(&self.device, self.config.format)
+        let builder = RenderPipelineBuilder::new(&self.device, self.config.format)
+            .sample_count(self.sample_count);
+        let builder = match self.pipeline_cache.as_ref() {
+            Some(cache) => builder.cache(cache.cache()),
+            None => builder,
+        };
+
+        match self.depth_format {
+            Some(format) => builder.depth_format(format),
+            None => builder,
+        }
+    }
+
+    pub fn compute_pipeline(&self) -> ComputePipelineBuilder<'_> {
+        let builder = ComputePipelineBuilder::new(&self.device);
+        match self.pipeline_cache.as_ref() {
+            Some(cache) => builder.cache(cache.cache()),
+            None => builder,
+        }
+    }
+
+    /// Returns the pipeline cache used by [`Context::render_pipeline`] and
+    /// [`Context::compute_pipeline`], if [`GraphicsBuilder::pipeline_cache_dir`] is set and
+    /// the backend supports it.
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache
+            .as_ref()
+            .map(pipeline_cache::PipelineCache::cache)
     }
 
-    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+    /// Writes the pipeline cache back to [`GraphicsBuilder::pipeline_cache_dir`].
+    ///
+    /// Does nothing if pipeline caching is disabled or unsupported. Failures are logged.
+    pub fn save_pipeline_cache(&self) {
+        if let Some(cache) = self.pipeline_cache.as_ref() {
+            cache.save();
+        }
+    }
+
+    /// Changes the present mode, falling back through the configured present mode fallbacks
+    /// if the surface does not support it.
+    ///
+    /// Returns the present mode that actually took effect.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let mode = negotiate_present_mode(
+            mode,
+            &self.graphics.present_mode_fallbacks,
+            &self.supported_present_modes(),
+        );
         self.config.present_mode = mode;
-        self.surface.configure(&self.device, &self.config);
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
         tracing::info!("Present mode changed to {:?}", mode);
+        mode
+    }
+
+    /// Returns the present modes supported by the surface, or an empty list when headless.
+    pub fn supported_present_modes(&self) -> Vec<wgpu::PresentMode> {
+        self.surface
+            .as_ref()
+            .map(|surface| surface.get_capabilities(&self.adapter).present_modes)
+            .unwrap_or_default()
     }
 
     pub fn present_mode(&self) -> wgpu::PresentMode {
@@ -222,22 +1346,25 @@ impl Context {
     }
 
     pub fn set_fullscreen(&self, fullscreen: bool) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
         if fullscreen {
-            self.window
-                .set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+            window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
         } else {
-            self.window.set_fullscreen(None);
+            window.set_fullscreen(None);
         }
         tracing::info!("Fullscreen mode: {}", fullscreen);
     }
 
     pub fn toggle_fullscreen(&self) {
-        let is_fullscreen = self.window.fullscreen().is_some();
-        self.set_fullscreen(!is_fullscreen);
+        self.set_fullscreen(!self.is_fullscreen());
     }
 
     pub fn is_fullscreen(&self) -> bool {
-        self.window.fullscreen().is_some()
+        self.window
+            .as_ref()
+            .is_some_and(|window| window.fullscreen().is_some())
     }
 
     pub fn width(&self) -> u32 {
@@ -252,45 +1379,537 @@ impl Context {
         self.size.width as f32 / self.size.height as f32
     }
 
+    /// Requests a new window size, or resizes the offscreen target of a headless context.
     pub fn set_window_size(&mut self, width: u32, height: u32) {
         let new_size = winit::dpi::PhysicalSize::new(width, height);
-        let _ = self.window.request_inner_size(new_size);
-        tracing::info!("Requested window size: {}x{}", width, height);
+        match self.window.as_ref() {
+            Some(window) => {
+                let _ = window.request_inner_size(new_size);
+                tracing::info!("Requested window size: {}x{}", width, height);
+            }
+            None => self.resize(new_size),
+        }
     }
 
     pub fn set_maximized(&self, maximized: bool) {
-        self.window.set_maximized(maximized);
+        if let Some(window) = self.window.as_ref() {
+            window.set_maximized(maximized);
+        }
     }
 
     pub fn is_maximized(&self) -> bool {
-        self.window.is_maximized()
+        self.window
+            .as_ref()
+            .is_some_and(|window| window.is_maximized())
     }
 
     pub fn set_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = self.window.as_ref() {
+            window.set_title(title);
+        }
     }
 
     pub fn set_resizable(&self, resizable: bool) {
-        self.window.set_resizable(resizable);
+        if let Some(window) = self.window.as_ref() {
+            window.set_resizable(resizable);
+        }
+    }
+
+    pub fn set_decorations(&self, decorations: bool) {
+        if let Some(window) = self.window.as_ref() {
+            window.set_decorations(decorations);
+        }
+    }
+
+    /// Returns the composite alpha mode the surface was configured with.
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.config.alpha_mode
     }
 
     pub fn set_min_size(&self, width: u32, height: u32) {
-        self.window
-            .set_min_inner_size(Some(winit::dpi::LogicalSize::new(width, height)));
+        if let Some(window) = self.window.as_ref() {
+            window.set_min_inner_size(Some(winit::dpi::LogicalSize::new(width, height)));
+        }
     }
 
     pub fn set_max_size(&self, width: u32, height: u32) {
-        self.window
-            .set_max_inner_size(Some(winit::dpi::LogicalSize::new(width, height)));
+        if let Some(window) = self.window.as_ref() {
+            window.set_max_inner_size(Some(winit::dpi::LogicalSize::new(width, height)));
+        }
     }
 
     pub fn clear_min_size(&self) {
-        self.window
-            .set_min_inner_size(None::<winit::dpi::LogicalSize<u32>>);
+        if let Some(window) = self.window.as_ref() {
+            window.set_min_inner_size(None::<winit::dpi::LogicalSize<u32>>);
+        }
     }
 
     pub fn clear_max_size(&self) {
-        self.window
-            .set_max_inner_size(None::<winit::dpi::LogicalSize<u32>>);
+        if let Some(window) = self.window.as_ref() {
+            window.set_max_inner_size(None::<winit::dpi::LogicalSize<u32>>);
+        }
+    }
+}
+
+async fn try_backends<F, Fut>(graphics: &GraphicsBuilder, mut init: F) -> Result<Context>
+where
+    F: FnMut(wgpu::Backends) -> Fut,
+    Fut: std::future::Future<Output = Result<Context>>,
+{
+    let mut result = init(graphics.backends).await;
+
+    for &backends in &graphics.backend_fallbacks {
+        let Err(e) = &result else {
+            break;
+        };
+        tracing::warn!(
+            "Graphics initialization failed: {}, falling back to {:?}",
+            e,
+            backends
+        );
+        result = init(backends).await;
+    }
+
+    result
+}
+
+async fn request_device(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    graphics: &GraphicsBuilder,
+    compatible_surface: Option<&wgpu::Surface<'_>>,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let adapter = match graphics.adapter.as_ref() {
+        Some(selector) => adapter::select_adapter(instance, backends, selector, compatible_surface)
+            .map_err(|available| Error::NoMatchingAdapter {
+                selector: selector.clone(),
+                available,
+            })?,
+        None => {
+            instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: graphics.power_preference,
+                    compatible_surface,
+                    force_fallback_adapter: graphics.force_fallback_adapter,
+                })
+                .await?
+        }
+    };
+
+    let mut optional_features = graphics.optional_features;
+    if !matches!(graphics.msaa_samples, 0 | 1 | 4) {
+        // Sample counts other than 1 and 4 need adapter specific format features.
+        optional_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+    }
+    if let Some(format) = graphics.depth_format {
+        optional_features |= format.required_features();
+    }
+    if graphics.profiling {
+        optional_features |= wgpu::Features::TIMESTAMP_QUERY;
+    }
+    if graphics.pipeline_cache_dir.is_some() {
+        optional_features |= wgpu::Features::PIPELINE_CACHE;
+    }
+
+    let supported_features = adapter.features();
+    let missing_optional = optional_features - supported_features;
+    if !missing_optional.is_empty() {
+        tracing::info!("Optional features not available: {:?}", missing_optional);
+    }
+
+    let required_features = graphics.required_features | (optional_features & supported_features);
+    let required_limits = graphics.limits.resolve(&adapter.limits());
+
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("Soyuz Device"),
+            required_features,
+            required_limits: required_limits.clone(),
+            experimental_features: wgpu::ExperimentalFeatures::default(),
+            memory_hints: wgpu::MemoryHints::default(),
+            trace: wgpu::Trace::default(),
+        })
+        .await
+        .map_err(|source| Error::RequestDevice {
+            source,
+            requested_features: required_features,
+            supported_features,
+            requested_limits: Box::new(required_limits),
+            supported_limits: Box::new(adapter.limits()),
+        })?;
+
+    Ok((adapter, device, queue))
+}
+
+fn watch_device_lost(device: &wgpu::Device) -> Arc<AtomicBool> {
+    let device_lost = Arc::new(AtomicBool::new(false));
+    let flag = device_lost.clone();
+
+    device.set_device_lost_callback(move |reason, message| match reason {
+        wgpu::DeviceLostReason::Destroyed => {
+            tracing::debug!("Device destroyed: {}", message);
+        }
+        wgpu::DeviceLostReason::Unknown => {
+            tracing::error!("Device lost: {}", message);
+            flag.store(true, Ordering::Release);
+        }
+    });
+
+    device_lost
+}
+
+/// Logs GPU errors raised outside of an error scope instead of panicking.
+fn report_uncaptured_errors(device: &wgpu::Device, adapter: &wgpu::Adapter) {
+    let info = adapter.get_info();
+
+    device.on_uncaptured_error(Arc::new(move |error| {
+        tracing::error!(
+            "Uncaptured GPU error on {} ({:?}): {}",
+            info.name,
+            info.backend,
+            error
+        );
+    }));
+}
+
+fn surface_config(
+    graphics: &GraphicsBuilder,
+    caps: &wgpu::SurfaceCapabilities,
+    size: winit::dpi::PhysicalSize<u32>,
+) -> Result<wgpu::SurfaceConfiguration> {
+    let format = select_surface_format(&graphics.surface_formats, caps)?;
+    let present_mode = negotiate_present_mode(
+        graphics.present_mode,
+        &graphics.present_mode_fallbacks,
+        &caps.present_modes,
+    );
+    let alpha_mode = select_alpha_mode(&graphics.alpha_modes, &caps.alpha_modes);
+
+    let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+    if graphics.capture_surface && caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+        usage |= wgpu::TextureUsages::COPY_SRC;
+    }
+
+    Ok(wgpu::SurfaceConfiguration {
+        usage,
+        format,
+        width: size.width,
+        height: size.height,
+        present_mode,
+        alpha_mode,
+        view_formats: compatible_view_formats(format, &graphics.view_formats),
+        desired_maximum_frame_latency: 2,
+    })
+}
+
+fn select_surface_format(
+    preferred: &[wgpu::TextureFormat],
+    caps: &wgpu::SurfaceCapabilities,
+) -> Result<wgpu::TextureFormat> {
+    let format = if preferred.is_empty() {
+        caps.formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .or_else(|| caps.formats.first().copied())
+    } else {
+        preferred.iter().copied().find(|f| caps.formats.contains(f))
+    };
+
+    format.ok_or_else(|| Error::UnsupportedSurfaceFormat {
+        requested: preferred.to_vec(),
+        supported: caps.formats.clone(),
+    })
+}
+
+fn negotiate_present_mode(
+    requested: wgpu::PresentMode,
+    fallbacks: &[wgpu::PresentMode],
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    let is_supported = |mode: &wgpu::PresentMode| {
+        matches!(
+            mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        ) || supported.contains(mode)
+    };
+
+    if supported.is_empty() || is_supported(&requested) {
+        return requested;
+    }
+
+    let mode = fallbacks
+        .iter()
+        .copied()
+        .find(is_supported)
+        .unwrap_or(wgpu::PresentMode::Fifo);
+
+    tracing::warn!(
+        "Present mode {:?} is not supported, using {:?} instead",
+        requested,
+        mode
+    );
+
+    mode
+}
+
+fn select_alpha_mode(
+    preferred: &[wgpu::CompositeAlphaMode],
+    supported: &[wgpu::CompositeAlphaMode],
+) -> wgpu::CompositeAlphaMode {
+    if preferred.is_empty() {
+        return wgpu::CompositeAlphaMode::Auto;
+    }
+
+    preferred
+        .iter()
+        .copied()
+        .find(|mode| *mode == wgpu::CompositeAlphaMode::Auto || supported.contains(mode))
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "None of the alpha modes {:?} are supported (supported: {:?}), using Auto",
+                preferred,
+                supported
+            );
+            wgpu::CompositeAlphaMode::Auto
+        })
+}
+
+fn compatible_view_formats(
+    format: wgpu::TextureFormat,
+    requested: &[wgpu::TextureFormat],
+) -> Vec<wgpu::TextureFormat> {
+    requested
+        .iter()
+        .copied()
+        .filter(|view_format| {
+            let compatible = view_format.remove_srgb_suffix() == format.remove_srgb_suffix();
+            if !compatible {
+                tracing::warn!(
+                    "Ignoring view format {:?}, incompatible with {:?}",
+                    view_format,
+                    format
+                );
+            }
+            compatible && *view_format != format
+        })
+        .collect()
+}
+
+fn log_adapter_info(adapter: &wgpu::Adapter) {
+    let info = adapter.get_info();
+    tracing::info!("GPU: {}", info.name);
+    tracing::info!(
+        "Adapter: {:?} on {:?} (driver: {} {})",
+        info.device_type,
+        info.backend,
+        info.driver,
+        info.driver_info
+    );
+}
+
+fn create_offscreen_target(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &config.view_formats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::{CompositeAlphaMode, PresentMode, TextureFormat};
+
+    fn caps(formats: &[TextureFormat]) -> wgpu::SurfaceCapabilities {
+        wgpu::SurfaceCapabilities {
+            formats: formats.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn surface_format_prefers_srgb_then_first() {
+        let formats = [TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb];
+        assert_eq!(
+            select_surface_format(&[], &caps(&formats)).unwrap(),
+            TextureFormat::Bgra8UnormSrgb
+        );
+
+        let formats = [TextureFormat::Rgba16Float, TextureFormat::Bgra8Unorm];
+        assert_eq!(
+            select_surface_format(&[], &caps(&formats)).unwrap(),
+            TextureFormat::Rgba16Float
+        );
+    }
+
+    #[test]
+    fn surface_format_takes_first_supported_preference() {
+        let formats = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgb10a2Unorm];
+        let preferred = [
+            TextureFormat::Rgba16Float,
+            TextureFormat::Rgb10a2Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+        ];
+        assert_eq!(
+            select_surface_format(&preferred, &caps(&formats)).unwrap(),
+            TextureFormat::Rgb10a2Unorm
+        );
+
+        // Preferences are not extended with the sRGB default.
+        let preferred = [TextureFormat::Rgba16Float];
+        match select_surface_format(&preferred, &caps(&formats)) {
+            Err(Error::UnsupportedSurfaceFormat {
+                requested,
+                supported,
+            }) => {
+                assert_eq!(requested, preferred);
+                assert_eq!(supported, formats);
+            }
+            other => panic!("expected UnsupportedSurfaceFormat, got {:?}", other),
+        }
+
+        assert!(matches!(
+            select_surface_format(&[], &caps(&[])),
+            Err(Error::UnsupportedSurfaceFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn present_mode_falls_back_in_order() {
+        let supported = [PresentMode::Fifo, PresentMode::Immediate];
+
+        assert_eq!(
+            negotiate_present_mode(PresentMode::Immediate, &[], &supported),
+            PresentMode::Immediate
+        );
+        assert_eq!(
+            negotiate_present_mode(
+                PresentMode::Mailbox,
+                &[
+                    PresentMode::FifoRelaxed,
+                    PresentMode::Immediate,
+                    PresentMode::Fifo
+                ],
+                &supported
+            ),
+            PresentMode::Immediate
+        );
+        assert_eq!(
+            negotiate_present_mode(
+                PresentMode::Mailbox,
+                &[PresentMode::FifoRelaxed],
+                &supported
+            ),
+            PresentMode::Fifo
+        );
+        // Auto modes are always available, as wgpu resolves them itself.
+        assert_eq!(
+            negotiate_present_mode(
+                PresentMode::Mailbox,
+                &[PresentMode::AutoNoVsync],
+                &supported
+            ),
+            PresentMode::AutoNoVsync
+        );
+        assert_eq!(
+            negotiate_present_mode(PresentMode::AutoVsync, &[], &supported),
+            PresentMode::AutoVsync
+        );
+        // Unknown capabilities, as for offscreen targets, accept any mode.
+        assert_eq!(
+            negotiate_present_mode(PresentMode::Mailbox, &[], &[]),
+            PresentMode::Mailbox
+        );
+    }
+
+    #[test]
+    fn alpha_mode_takes_first_supported_preference() {
+        let supported = [
+            CompositeAlphaMode::Opaque,
+            CompositeAlphaMode::PreMultiplied,
+        ];
+
+        assert_eq!(select_alpha_mode(&[], &supported), CompositeAlphaMode::Auto);
+        assert_eq!(
+            select_alpha_mode(
+                &[
+                    CompositeAlphaMode::PostMultiplied,
+                    CompositeAlphaMode::PreMultiplied,
+                    CompositeAlphaMode::Opaque,
+                ],
+                &supported
+            ),
+            CompositeAlphaMode::PreMultiplied
+        );
+        assert_eq!(
+            select_alpha_mode(&[CompositeAlphaMode::Inherit], &supported),
+            CompositeAlphaMode::Auto
+        );
+    }
+
+    #[test]
+    fn view_formats_keep_srgb_variants_only() {
+        assert_eq!(
+            compatible_view_formats(
+                TextureFormat::Bgra8Unorm,
+                &[
+                    TextureFormat::Bgra8UnormSrgb,
+                    TextureFormat::Bgra8Unorm,
+                    TextureFormat::Rgba8UnormSrgb,
+                ]
+            ),
+            [TextureFormat::Bgra8UnormSrgb]
+        );
+        assert_eq!(
+            compatible_view_formats(TextureFormat::Rgba8UnormSrgb, &[TextureFormat::Rgba8Unorm]),
+            [TextureFormat::Rgba8Unorm]
+        );
+        assert!(compatible_view_formats(TextureFormat::Rgba16Float, &[]).is_empty());
+    }
+
+    #[test]
+    fn limits_policy_resolves_against_adapter() {
+        let supported = wgpu::Limits {
+            max_texture_dimension_2d: 16384,
+            max_bind_groups: 8,
+            ..wgpu::Limits::default()
+        };
+
+        let exact = wgpu::Limits {
+            max_bind_groups: 2,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(
+            LimitsPolicy::Exact(exact.clone()).resolve(&supported),
+            exact
+        );
+        assert_eq!(LimitsPolicy::AdapterMaximum.resolve(&supported), supported);
+
+        let downlevel = LimitsPolicy::DownlevelDefaults.resolve(&supported);
+        assert_eq!(downlevel.max_texture_dimension_2d, 16384);
+        assert_eq!(
+            downlevel.max_bind_groups,
+            wgpu::Limits::downlevel_defaults().max_bind_groups
+        );
+
+        let webgl2 = LimitsPolicy::DownlevelWebgl2Defaults.resolve(&supported);
+        assert_eq!(webgl2.max_texture_dimension_2d, 16384);
+        assert_eq!(
+            webgl2.max_storage_buffers_per_shader_stage,
+            wgpu::Limits::downlevel_webgl2_defaults().max_storage_buffers_per_shader_stage
+        );
     }
 }
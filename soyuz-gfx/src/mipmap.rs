@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::blit::Blitter;
+use crate::{
+    BindGroupBuilder, BindGroupLayoutBuilder, ComputePassBuilder, ComputePassExt,
+    ComputePipelineBuilder, Error, Result, Shader,
+};
+
+/// Fills the mip levels of 2D textures and texture arrays on the GPU, each level
+/// downsampled from the one above it.
+///
+/// Textures with `RENDER_ATTACHMENT` usage are downsampled with a blit render pass
+/// through views of their own format, so sRGB textures are filtered in linear space.
+/// Other textures need `STORAGE_BINDING` usage and a storage-capable format, and are
+/// box filtered with a compute shader. Pipelines are created lazily and cached per format.
+#[derive(Default)]
+pub struct MipmapGenerator {
+    pipelines: Mutex<Pipelines>,
+}
+
+#[derive(Default)]
+struct Pipelines {
+    blitter: Option<Blitter>,
+    array_blitter: Option<Blitter>,
+    compute: HashMap<wgpu::TextureFormat, ComputeMipmap>,
+}
+
+struct ComputeMipmap {
+    shader: Shader,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl MipmapGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the generation of every mip level below the base level of `texture` into
+    /// `encoder`.
+    ///
+    /// The texture must have either `TEXTURE_BINDING | RENDER_ATTACHMENT` usage with a
+    /// filterable format, or `STORAGE_BINDING` usage with a storage-capable format.
+    pub fn generate(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Result<()> {
+        if texture.mip_level_count() <= 1 {
+            return Ok(());
+        }
+
+        let format = texture.format();
+        let usage = texture.usage();
+        if texture.dimension() != wgpu::TextureDimension::D2 || texture.sample_count() != 1 {
+            return Err(Error::UnsupportedMipmapTexture(format));
+        }
+
+        let features = format.guaranteed_format_features(device.features());
+        if usage
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT)
+            && features
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+        {
+            // Array textures are sampled through `D2Array` views of every layer, with the
+            // layer picked in the shader, as some backends can neither view a layer as a
+            // plain 2D texture nor sample single-layer views of layers other than the first.
+            let mut pipelines = self.pipelines.lock().unwrap();
+            if texture.depth_or_array_layers() > 1 {
+                let blitter = pipelines
+                    .array_blitter
+                    .get_or_insert_with(|| Blitter::new_array(device));
+                for level in level_views(texture, wgpu::TextureViewDimension::D2Array) {
+                    blitter.blit_layer(
+                        device,
+                        encoder,
+                        &level.source,
+                        level.layer,
+                        &level.target,
+                        format,
+                    );
+                }
+            } else {
+                let blitter = pipelines
+                    .blitter
+                    .get_or_insert_with(|| Blitter::new(device));
+                for level in level_views(texture, wgpu::TextureViewDimension::D2) {
+                    blitter.blit(device, encoder, &level.source, &level.target, format);
+                }
+            }
+            return Ok(());
+        }
+
+        if usage.contains(wgpu::TextureUsages::STORAGE_BINDING)
+            && features.flags.contains(
+                wgpu::TextureFormatFeatureFlags::STORAGE_READ_ONLY
+                    | wgpu::TextureFormatFeatureFlags::STORAGE_WRITE_ONLY,
+            )
+            && let Some(name) = storage_format_name(format)
+        {
+            let mut pipelines = self.pipelines.lock().unwrap();
+            let mipmap = pipelines
+                .compute
+                .entry(format)
+                .or_insert_with(|| ComputeMipmap::new(device, format, name));
+            mipmap.generate(device, encoder, texture);
+            return Ok(());
+        }
+
+        Err(Error::UnsupportedMipmapTexture(format))
+    }
+}
+
+impl ComputeMipmap {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, name: &str) -> Self {
+        let source = include_str!("mipmap.wgsl").replace("STORAGE_FORMAT", name);
+        let shader = Shader::from_wgsl(device, &source);
+
+        let bind_group_layout = BindGroupLayoutBuilder::new(device)
+            .storage_texture(
+                0,
+                wgpu::ShaderStages::COMPUTE,
+                format,
+                wgpu::StorageTextureAccess::ReadOnly,
+            )
+            .storage_texture(
+                1,
+                wgpu::ShaderStages::COMPUTE,
+                format,
+                wgpu::StorageTextureAccess::WriteOnly,
+            )
+            .label("Mipmap Bind Group Layout")
+            .build();
+
+        let pipeline = ComputePipelineBuilder::new(device)
+            .shader_from(&shader)
+            .bind_group_layout(&bind_group_layout)
+            .label("Mipmap Pipeline")
+            .build();
+
+        Self {
+            shader,
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    fn generate(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        for level in level_views(texture, wgpu::TextureViewDimension::D2) {
+            let bind_group = BindGroupBuilder::new(device, &self.bind_group_layout)
+                .texture(0, &level.source)
+                .texture(1, &level.target)
+                .label("Mipmap Bind Group")
+                .build();
+
+            let mut pass = ComputePassBuilder::new(encoder)
+                .label("Mipmap Pass")
+                .begin();
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_size(&self.shader, "cs_main", [level.width, level.height, 1]);
+        }
+    }
+}
+
+/// A mip level of one array layer and a view of the level above it to downsample from.
+struct LevelViews {
+    source: wgpu::TextureView,
+    target: wgpu::TextureView,
+    layer: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Returns the views for every level below the base level of every array layer, in the
+/// order they must be filled. Targets are single-layer `D2` views. Sources are views of
+/// the same layer if `source_dimension` is `D2`, and cover every layer if it is `D2Array`.
+fn level_views(
+    texture: &wgpu::Texture,
+    source_dimension: wgpu::TextureViewDimension,
+) -> Vec<LevelViews> {
+    let view = |layer: u32, level: u32, dimension: wgpu::TextureViewDimension| {
+        let (base_array_layer, array_layer_count) = match dimension {
+            wgpu::TextureViewDimension::D2Array => (0, None),
+            _ => (layer, Some(1)),
+        };
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Mipmap View"),
+            dimension: Some(dimension),
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            base_array_layer,
+            array_layer_count,
+            ..Default::default()
+        })
+    };
+
+    let size = texture.size();
+    (0..size.depth_or_array_layers)
+        .flat_map(|layer| {
+            (1..texture.mip_level_count()).map(move |level| {
+                let level_size = size.mip_level_size(level, wgpu::TextureDimension::D2);
+                LevelViews {
+                    source: view(layer, level - 1, source_dimension),
+                    target: view(layer, level, wgpu::TextureViewDimension::D2),
+                    layer,
+                    width: level_size.width,
+                    height: level_size.height,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Returns the WGSL name of `format` if it can be used as a storage texture.
+fn storage_format_name(format: wgpu::TextureFormat) -> Option<&'static str> {
+    use wgpu::TextureFormat::*;
+
+    Some(match format {
+        Rgba8Unorm => "rgba8unorm",
+        Rgba8Snorm => "rgba8snorm",
+        Rgba16Float => "rgba16float",
+        Rgba32Float => "rgba32float",
+        R32Float => "r32float",
+        Rg32Float => "rg32float",
+        _ => return None,
+    })
+}
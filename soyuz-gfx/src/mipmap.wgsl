@@ -0,0 +1,23 @@
+// `STORAGE_FORMAT` is replaced with the WGSL name of the texture format.
+
+@group(0) @binding(0)
+var src_texture: texture_storage_2d<STORAGE_FORMAT, read>;
+@group(0) @binding(1)
+var dst_texture: texture_storage_2d<STORAGE_FORMAT, write>;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dst_size = textureDimensions(dst_texture);
+    if (id.x >= dst_size.x || id.y >= dst_size.y) {
+        return;
+    }
+
+    let last = textureDimensions(src_texture) - vec2<u32>(1u);
+    let base = id.xy * 2u;
+    let sum = textureLoad(src_texture, min(base, last))
+        + textureLoad(src_texture, min(base + vec2<u32>(1u, 0u), last))
+        + textureLoad(src_texture, min(base + vec2<u32>(0u, 1u), last))
+        + textureLoad(src_texture, min(base + vec2<u32>(1u, 1u), last));
+
+    textureStore(dst_texture, id.xy, sum * 0.25);
+}
@@ -1,10 +1,17 @@
+use crate::Shader;
+use crate::profiler::Profiler;
+
 pub struct RenderPassBuilder<'a> {
     encoder: &'a mut wgpu::CommandEncoder,
     view: &'a wgpu::TextureView,
+    resolve_target: Option<&'a wgpu::TextureView>,
     clear_color: Option<wgpu::Color>,
     label: Option<&'a str>,
     load_op: Option<wgpu::LoadOp<wgpu::Color>>,
     depth_stencil: Option<wgpu::RenderPassDepthStencilAttachment<'a>>,
+    depth_target: Option<(&'a wgpu::TextureView, wgpu::TextureFormat)>,
+    timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'a>>,
+    profiler: Option<&'a Profiler>,
 }
 
 impl<'a> RenderPassBuilder<'a> {
@@ -12,13 +19,23 @@ impl<'a> RenderPassBuilder<'a> {
         Self {
             encoder,
             view,
+            resolve_target: None,
             clear_color: None,
             label: None,
             load_op: None,
             depth_stencil: None,
+            depth_target: None,
+            timestamp_writes: None,
+            profiler: None,
         }
     }
 
+    /// Resolves the multisampled color attachment into `target` at the end of the pass.
+    pub fn resolve_target(mut self, target: &'a wgpu::TextureView) -> Self {
+        self.resolve_target = Some(target);
+        self
+    }
+
     pub fn clear(mut self, color: wgpu::Color) -> Self {
         self.clear_color = Some(color);
         self
@@ -29,6 +46,11 @@ impl<'a> RenderPassBuilder<'a> {
         self
     }
 
+    pub fn clear_rgba(mut self, r: f64, g: f64, b: f64, a: f64) -> Self {
+        self.clear_color = Some(wgpu::Color { r, g, b, a });
+        self
+    }
+
     pub fn load(mut self) -> Self {
         self.load_op = Some(wgpu::LoadOp::Load);
         self
@@ -44,16 +66,71 @@ impl<'a> RenderPassBuilder<'a> {
         self
     }
 
+    /// Sets the depth buffer attached by [`RenderPassBuilder::with_depth`].
+    pub fn depth_target(
+        mut self,
+        view: &'a wgpu::TextureView,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        self.depth_target = Some((view, format));
+        self
+    }
+
+    /// Attaches the depth buffer, clearing depth to `clear_value` (and stencil to 0 if the
+    /// format has a stencil aspect).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no depth target is set, either with [`RenderPassBuilder::depth_target`] or
+    /// through `GraphicsBuilder::depth_format` when the pass comes from `Context::render_pass`.
+    pub fn with_depth(mut self, clear_value: f32) -> Self {
+        let (view, format) = self
+            .depth_target
+            .expect("with_depth() requires a depth target. Use GraphicsBuilder::depth_format() or depth_target().");
+
+        self.depth_stencil = Some(wgpu::RenderPassDepthStencilAttachment {
+            view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(clear_value),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: format.has_stencil_aspect().then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(0),
+                store: wgpu::StoreOp::Store,
+            }),
+        });
+        self
+    }
+
+    pub fn timestamp_writes(
+        mut self,
+        timestamp_writes: wgpu::RenderPassTimestampWrites<'a>,
+    ) -> Self {
+        self.timestamp_writes = Some(timestamp_writes);
+        self
+    }
+
+    /// Times the pass with the context's profiler, under its label.
+    pub(crate) fn profiler(mut self, profiler: &'a Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
     pub fn begin(self) -> wgpu::RenderPass<'a> {
         let load_op = self
             .load_op
             .unwrap_or_else(|| wgpu::LoadOp::Clear(self.clear_color.unwrap_or(wgpu::Color::BLACK)));
+        let timestamp_writes = self.timestamp_writes.or_else(|| {
+            self.profiler.and_then(|profiler| {
+                profiler.render_timestamp_writes(self.label.unwrap_or("Render Pass"))
+            })
+        });
 
         self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: self.label,
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: self.view,
-                resolve_target: None,
+                resolve_target: self.resolve_target,
                 ops: wgpu::Operations {
                     load: load_op,
                     store: wgpu::StoreOp::Store,
@@ -61,8 +138,100 @@ impl<'a> RenderPassBuilder<'a> {
                 depth_slice: None,
             })],
             depth_stencil_attachment: self.depth_stencil,
-            timestamp_writes: None,
+            timestamp_writes,
             occlusion_query_set: None,
         })
     }
 }
+
+pub struct ComputePassBuilder<'a> {
+    encoder: &'a mut wgpu::CommandEncoder,
+    label: Option<&'a str>,
+    timestamp_writes: Option<wgpu::ComputePassTimestampWrites<'a>>,
+    profiler: Option<&'a Profiler>,
+}
+
+impl<'a> ComputePassBuilder<'a> {
+    pub fn new(encoder: &'a mut wgpu::CommandEncoder) -> Self {
+        Self {
+            encoder,
+            label: None,
+            timestamp_writes: None,
+            profiler: None,
+        }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn timestamp_writes(
+        mut self,
+        timestamp_writes: wgpu::ComputePassTimestampWrites<'a>,
+    ) -> Self {
+        self.timestamp_writes = Some(timestamp_writes);
+        self
+    }
+
+    /// Times the pass with the context's profiler, under its label.
+    pub(crate) fn profiler(mut self, profiler: &'a Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    pub fn begin(self) -> wgpu::ComputePass<'a> {
+        let timestamp_writes = self.timestamp_writes.or_else(|| {
+            self.profiler.and_then(|profiler| {
+                profiler.compute_timestamp_writes(self.label.unwrap_or("Compute Pass"))
+            })
+        });
+
+        self.encoder
+            .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: self.label,
+                timestamp_writes,
+            })
+    }
+}
+
+/// Dispatch helpers for compute passes.
+pub trait ComputePassExt {
+    /// Dispatches enough workgroups to cover `size` invocations along each axis, using the
+    /// `@workgroup_size` that `shader` declares for the compute entry point `entry`.
+    /// Shaders must skip the invocations past `size` in the last workgroups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the workgroup size is unknown, see [`Shader::workgroup_size`]. Dispatch
+    /// with [`workgroup_count`] when it depends on pipeline-overridable constants.
+    fn dispatch_size(&mut self, shader: &Shader, entry: &str, size: [u32; 3]);
+}
+
+impl ComputePassExt for wgpu::ComputePass<'_> {
+    fn dispatch_size(&mut self, shader: &Shader, entry: &str, size: [u32; 3]) {
+        let workgroup_size = shader.workgroup_size(entry).unwrap_or_else(|| {
+            panic!(
+                "shader has no compute entry point `{}` with a fixed workgroup size",
+                entry
+            )
+        });
+        let [x, y, z] = workgroup_count(size, workgroup_size);
+        self.dispatch_workgroups(x, y, z);
+    }
+}
+
+/// Returns the number of workgroups of `workgroup_size` needed to cover `size`
+/// invocations along each axis.
+///
+/// # Panics
+///
+/// Panics if a workgroup dimension is zero.
+pub fn workgroup_count(size: [u32; 3], workgroup_size: [u32; 3]) -> [u32; 3] {
+    assert!(
+        workgroup_size.iter().all(|&n| n > 0),
+        "workgroup size {:?} has a zero dimension",
+        workgroup_size
+    );
+    [0, 1, 2].map(|axis| size[axis].div_ceil(workgroup_size[axis]))
+}
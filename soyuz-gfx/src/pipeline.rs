@@ -1,4 +1,8 @@
 use super::shader::Shader;
+use crate::error::capture_errors;
+use crate::reflect::ShaderReflection;
+use crate::uniform::Uniform;
+use crate::{Error, Result};
 
 pub struct RenderPipelineBuilder<'a> {
     device: &'a wgpu::Device,
@@ -6,6 +10,9 @@ pub struct RenderPipelineBuilder<'a> {
     shader: Option<&'a wgpu::ShaderModule>,
     vertex_shader: Option<(&'a wgpu::ShaderModule, &'a str)>,
     fragment_shader: Option<(&'a wgpu::ShaderModule, &'a str)>,
+    shader_reflection: Option<&'a ShaderReflection>,
+    vertex_reflection: Option<&'a ShaderReflection>,
+    fragment_reflection: Option<&'a ShaderReflection>,
     vertex_entry: Option<&'a str>,
     fragment_entry: Option<&'a str>,
 
@@ -17,9 +24,12 @@ pub struct RenderPipelineBuilder<'a> {
     default_write_mask: wgpu::ColorWrites,
 
     pipeline_layout: Option<&'a wgpu::PipelineLayout>,
+    bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
     primitive: wgpu::PrimitiveState,
     depth_stencil: Option<wgpu::DepthStencilState>,
+    depth_format: Option<wgpu::TextureFormat>,
     multisample: wgpu::MultisampleState,
+    cache: Option<&'a wgpu::PipelineCache>,
 
     label: Option<&'a str>,
 }
@@ -31,6 +41,9 @@ impl<'a> RenderPipelineBuilder<'a> {
             shader: None,
             vertex_shader: None,
             fragment_shader: None,
+            shader_reflection: None,
+            vertex_reflection: None,
+            fragment_reflection: None,
             vertex_entry: Some("vs_main"),
             fragment_entry: Some("fs_main"),
             vertex_buffers: Vec::new(),
@@ -39,35 +52,51 @@ impl<'a> RenderPipelineBuilder<'a> {
             default_blend: Some(wgpu::BlendState::REPLACE),
             default_write_mask: wgpu::ColorWrites::ALL,
             pipeline_layout: None,
+            bind_group_layouts: Vec::new(),
             primitive: wgpu::PrimitiveState::default(),
             depth_stencil: None,
+            depth_format: None,
             multisample: wgpu::MultisampleState::default(),
+            cache: None,
             label: None,
         }
     }
 
     pub fn shader(mut self, shader: &'a wgpu::ShaderModule) -> Self {
         self.shader = Some(shader);
+        self.shader_reflection = None;
+        self
+    }
+
+    /// Like [`RenderPipelineBuilder::shader`], keeping the shader's reflection data so
+    /// the pipeline can be checked against it.
+    pub fn shader_from(mut self, shader: &'a Shader) -> Self {
+        self.shader = Some(shader.module());
+        self.shader_reflection = shader.reflection();
         self
     }
 
     pub fn vertex_shader(mut self, module: &'a wgpu::ShaderModule, entry: &'a str) -> Self {
         self.vertex_shader = Some((module, entry));
+        self.vertex_reflection = None;
         self
     }
 
     pub fn fragment_shader(mut self, module: &'a wgpu::ShaderModule, entry: &'a str) -> Self {
         self.fragment_shader = Some((module, entry));
+        self.fragment_reflection = None;
         self
     }
 
     pub fn vertex_shader_from(mut self, shader: &'a Shader, entry: &'a str) -> Self {
         self.vertex_shader = Some((shader.module(), entry));
+        self.vertex_reflection = shader.reflection();
         self
     }
 
     pub fn fragment_shader_from(mut self, shader: &'a Shader, entry: &'a str) -> Self {
         self.fragment_shader = Some((shader.module(), entry));
+        self.fragment_reflection = shader.reflection();
         self
     }
 
@@ -106,11 +135,57 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    /// Appends a bind group layout to the pipeline layout created by `build()`, at the
+    /// next group index. Ignored when `pipeline_layout()` is set.
+    pub fn bind_group_layout(mut self, layout: &'a wgpu::BindGroupLayout) -> Self {
+        self.bind_group_layouts.push(layout);
+        self
+    }
+
+    /// Appends bind group layouts to the pipeline layout created by `build()`, at the
+    /// next group indices. Ignored when `pipeline_layout()` is set.
+    pub fn bind_group_layouts(mut self, layouts: &[&'a wgpu::BindGroupLayout]) -> Self {
+        self.bind_group_layouts.extend_from_slice(layouts);
+        self
+    }
+
+    /// Appends the bind group layout of `uniform`, at the next group index.
+    pub fn uniform<T: bytemuck::Pod>(self, uniform: &'a Uniform<T>) -> Self {
+        self.bind_group_layout(uniform.layout())
+    }
+
     pub fn depth_stencil(mut self, depth_stencil: wgpu::DepthStencilState) -> Self {
         self.depth_stencil = Some(depth_stencil);
         self
     }
 
+    /// Sets the format used by [`RenderPipelineBuilder::with_depth`].
+    pub fn depth_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.depth_format = Some(format);
+        self
+    }
+
+    /// Enables depth testing with depth writes and a `Less` comparison, matching the depth
+    /// buffer managed by the context (or the format set with `depth_format()`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no depth format is known.
+    pub fn with_depth(mut self) -> Self {
+        let format = self
+            .depth_format
+            .expect("with_depth() requires a depth format. Use GraphicsBuilder::depth_format() or depth_format().");
+
+        self.depth_stencil = Some(wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        });
+        self
+    }
+
     pub fn primitive(mut self, primitive: wgpu::PrimitiveState) -> Self {
         self.primitive = primitive;
         self
@@ -121,6 +196,11 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    pub fn sample_count(mut self, count: u32) -> Self {
+        self.multisample.count = count;
+        self
+    }
+
     pub fn no_fragment_shader(mut self) -> Self {
         self.fragment_shader = None;
         self.fragment_entry = None;
@@ -128,11 +208,80 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    pub fn cache(mut self, cache: &'a wgpu::PipelineCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn label(mut self, label: &'a str) -> Self {
         self.label = Some(label);
         self
     }
 
+    /// Checks that a vertex shader is set, and the configuration against the reflection
+    /// data of shaders set with `shader_from()`, `vertex_shader_from()` or
+    /// `fragment_shader_from()`: that the entry points exist for their stage, and that
+    /// every vertex input has a vertex buffer attribute of a matching type.
+    pub fn validate(&self) -> Result<()> {
+        if self.vertex_shader.is_none() && self.shader.is_none() {
+            return Err(Error::InvalidPipeline("no vertex shader".to_string()));
+        }
+
+        let mut mismatches = Vec::new();
+
+        let (vertex_reflection, vertex_entry) = match self.vertex_shader {
+            Some((_, entry)) => (self.vertex_reflection, Some(entry)),
+            None => (self.shader_reflection, self.vertex_entry),
+        };
+        if let Some(reflection) = vertex_reflection {
+            match reflection.find_entry_point(wgpu::ShaderStages::VERTEX, vertex_entry) {
+                Ok(entry_point) => mismatches.extend(
+                    entry_point.vertex_input_mismatches(
+                        self.vertex_buffers
+                            .iter()
+                            .flat_map(|buffer| buffer.attributes),
+                    ),
+                ),
+                Err(mismatch) => mismatches.push(mismatch),
+            }
+        }
+
+        let (fragment_reflection, fragment_entry) = match self.fragment_shader {
+            Some((_, entry)) => (self.fragment_reflection, Some(entry)),
+            None if self.shader.is_some() && self.fragment_entry.is_some() => {
+                (self.shader_reflection, self.fragment_entry)
+            }
+            None => (None, None),
+        };
+        if let Some(reflection) = fragment_reflection
+            && let Err(mismatch) =
+                reflection.find_entry_point(wgpu::ShaderStages::FRAGMENT, fragment_entry)
+        {
+            mismatches.push(mismatch);
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidPipeline(mismatches.join("; ")))
+        }
+    }
+
+    /// Builds the pipeline, returning a missing shader, mismatches found by
+    /// [`RenderPipelineBuilder::validate`] and validation errors instead of reporting them
+    /// to the device's uncaptured error handler.
+    pub fn try_build(self) -> Result<wgpu::RenderPipeline> {
+        self.validate()?;
+        let device = self.device;
+        capture_errors(device, || self.build())
+    }
+
+    /// Builds the pipeline. Mismatches found by [`RenderPipelineBuilder::validate`] are
+    /// logged before wgpu reports its own errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no vertex shader is set.
     pub fn build(self) -> wgpu::RenderPipeline {
         let (vertex_module, vertex_entry) = if let Some((module, entry)) = self.vertex_shader {
             (module, Some(entry))
@@ -142,6 +291,14 @@ impl<'a> RenderPipelineBuilder<'a> {
             panic!("Vertex shader must be provided. Use vertex_shader() or shader() method.");
         };
 
+        if let Err(e) = self.validate() {
+            tracing::error!(
+                "Render pipeline {}: {}",
+                self.label.unwrap_or("<unnamed>"),
+                e
+            );
+        }
+
         let color_targets = if self.color_targets.is_empty() {
             vec![Some(wgpu::ColorTargetState {
                 format: self.color_format,
@@ -168,10 +325,20 @@ impl<'a> RenderPipelineBuilder<'a> {
             None
         };
 
+        let derived_layout =
+            (self.pipeline_layout.is_none() && !self.bind_group_layouts.is_empty()).then(|| {
+                self.device
+                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: self.label,
+                        bind_group_layouts: &self.bind_group_layouts,
+                        push_constant_ranges: &[],
+                    })
+            });
+
         self.device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
                 label: self.label,
-                layout: self.pipeline_layout,
+                layout: self.pipeline_layout.or(derived_layout.as_ref()),
                 vertex: wgpu::VertexState {
                     module: vertex_module,
                     entry_point: vertex_entry,
@@ -188,7 +355,7 @@ impl<'a> RenderPipelineBuilder<'a> {
                 depth_stencil: self.depth_stencil,
                 multisample: self.multisample,
                 multiview: None,
-                cache: None,
+                cache: self.cache,
             })
     }
 }
@@ -198,3 +365,162 @@ struct FragmentStateHelper<'a> {
     entry_point: Option<&'a str>,
     targets: Vec<Option<wgpu::ColorTargetState>>,
 }
+
+pub struct ComputePipelineBuilder<'a> {
+    device: &'a wgpu::Device,
+
+    shader: Option<&'a wgpu::ShaderModule>,
+    reflection: Option<&'a ShaderReflection>,
+    entry: &'a str,
+    constants: Vec<(&'a str, f64)>,
+
+    pipeline_layout: Option<&'a wgpu::PipelineLayout>,
+    bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+    cache: Option<&'a wgpu::PipelineCache>,
+
+    label: Option<&'a str>,
+}
+
+impl<'a> ComputePipelineBuilder<'a> {
+    pub fn new(device: &'a wgpu::Device) -> Self {
+        Self {
+            device,
+            shader: None,
+            reflection: None,
+            entry: "cs_main",
+            constants: Vec::new(),
+            pipeline_layout: None,
+            bind_group_layouts: Vec::new(),
+            cache: None,
+            label: None,
+        }
+    }
+
+    pub fn shader(mut self, shader: &'a wgpu::ShaderModule) -> Self {
+        self.shader = Some(shader);
+        self.reflection = None;
+        self
+    }
+
+    /// Like [`ComputePipelineBuilder::shader`], keeping the shader's reflection data so
+    /// the entry point can be checked against it.
+    pub fn shader_from(mut self, shader: &'a Shader) -> Self {
+        self.shader = Some(shader.module());
+        self.reflection = shader.reflection();
+        self
+    }
+
+    /// Sets the entry point, `cs_main` by default.
+    pub fn entry(mut self, entry: &'a str) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Sets the value of a pipeline-overridable constant (`override name: T;`).
+    pub fn constant(mut self, name: &'a str, value: f64) -> Self {
+        self.constants.push((name, value));
+        self
+    }
+
+    pub fn pipeline_layout(mut self, layout: &'a wgpu::PipelineLayout) -> Self {
+        self.pipeline_layout = Some(layout);
+        self
+    }
+
+    /// Appends a bind group layout to the pipeline layout created by `build()`, at the
+    /// next group index. Ignored when `pipeline_layout()` is set.
+    pub fn bind_group_layout(mut self, layout: &'a wgpu::BindGroupLayout) -> Self {
+        self.bind_group_layouts.push(layout);
+        self
+    }
+
+    /// Appends bind group layouts to the pipeline layout created by `build()`, at the
+    /// next group indices. Ignored when `pipeline_layout()` is set.
+    pub fn bind_group_layouts(mut self, layouts: &[&'a wgpu::BindGroupLayout]) -> Self {
+        self.bind_group_layouts.extend_from_slice(layouts);
+        self
+    }
+
+    /// Appends the bind group layout of `uniform`, at the next group index.
+    pub fn uniform<T: bytemuck::Pod>(self, uniform: &'a Uniform<T>) -> Self {
+        self.bind_group_layout(uniform.layout())
+    }
+
+    pub fn cache(mut self, cache: &'a wgpu::PipelineCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Checks that a shader is set, and that the entry point exists as a compute entry
+    /// point if the shader was set with `shader_from()`.
+    pub fn validate(&self) -> Result<()> {
+        if self.shader.is_none() {
+            return Err(Error::InvalidPipeline("no compute shader".to_string()));
+        }
+
+        match self.reflection {
+            Some(reflection) => reflection
+                .find_entry_point(wgpu::ShaderStages::COMPUTE, Some(self.entry))
+                .map(|_| ())
+                .map_err(Error::InvalidPipeline),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the pipeline, returning a missing shader, mismatches found by
+    /// [`ComputePipelineBuilder::validate`] and validation errors instead of reporting
+    /// them to the device's uncaptured error handler.
+    pub fn try_build(self) -> Result<wgpu::ComputePipeline> {
+        self.validate()?;
+        let device = self.device;
+        capture_errors(device, || self.build())
+    }
+
+    /// Builds the pipeline. Mismatches found by [`ComputePipelineBuilder::validate`] are
+    /// logged before wgpu reports its own errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no shader is set.
+    pub fn build(self) -> wgpu::ComputePipeline {
+        let module = self
+            .shader
+            .expect("Compute shader must be provided. Use shader() or shader_from() method.");
+
+        if let Err(e) = self.validate() {
+            tracing::error!(
+                "Compute pipeline {}: {}",
+                self.label.unwrap_or("<unnamed>"),
+                e
+            );
+        }
+
+        let derived_layout =
+            (self.pipeline_layout.is_none() && !self.bind_group_layouts.is_empty()).then(|| {
+                self.device
+                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: self.label,
+                        bind_group_layouts: &self.bind_group_layouts,
+                        push_constant_ranges: &[],
+                    })
+            });
+
+        self.device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: self.label,
+                layout: self.pipeline_layout.or(derived_layout.as_ref()),
+                module,
+                entry_point: Some(self.entry),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &self.constants,
+                    ..Default::default()
+                },
+                cache: self.cache,
+            })
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+/// A `wgpu::PipelineCache` persisted to a file keyed by the adapter and driver.
+#[derive(Clone)]
+pub(crate) struct PipelineCache {
+    cache: wgpu::PipelineCache,
+    path: PathBuf,
+}
+
+impl PipelineCache {
+    /// Creates the cache from the data saved in `dir` by a previous run, if any.
+    ///
+    /// Returns `None` if the backend or device does not support pipeline caching.
+    pub(crate) fn load(device: &wgpu::Device, adapter: &wgpu::Adapter, dir: &Path) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+            tracing::debug!("Pipeline caching is not supported by this device");
+            return None;
+        }
+
+        let key = wgpu::util::pipeline_cache_key(&adapter.get_info())?;
+        let path = dir.join(key);
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tracing::warn!("Failed to read pipeline cache {}: {}", path.display(), e);
+                None
+            }
+        };
+
+        // SAFETY: the data can only have been written by `save` from a cache created for
+        // an adapter with the same `pipeline_cache_key`, which names the file. Data from
+        // another wgpu version or driver is rejected and replaced by an empty cache.
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("Soyuz Pipeline Cache"),
+                data: data.as_deref(),
+                fallback: true,
+            })
+        };
+
+        tracing::debug!(
+            "Pipeline cache {} {}",
+            path.display(),
+            if data.is_some() { "loaded" } else { "created" }
+        );
+
+        Some(Self { cache, path })
+    }
+
+    pub(crate) fn cache(&self) -> &wgpu::PipelineCache {
+        &self.cache
+    }
+
+    /// Writes the cache back to its file, replacing it atomically.
+    pub(crate) fn save(&self) {
+        let Some(data) = self.cache.get_data() else {
+            return;
+        };
+
+        let temp_path = self.path.with_extension("tmp");
+        let result = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&temp_path, &data))
+            .and_then(|()| std::fs::rename(&temp_path, &self.path));
+
+        match result {
+            Ok(()) => tracing::debug!(
+                "Saved {} byte pipeline cache to {}",
+                data.len(),
+                self.path.display()
+            ),
+            Err(e) => tracing::warn!(
+                "Failed to save pipeline cache to {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, mpsc};
+use std::time::Duration;
+
+/// Maximum number of timestamps written per frame, two per timed pass.
+const MAX_QUERIES: u32 = 256;
+
+/// Number of frames whose timestamps may be waiting for readback. Frames ending while
+/// all of them are in flight are not timed.
+const MAX_PENDING_FRAMES: usize = 3;
+
+/// GPU time spent in a pass, as measured with timestamp queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    pub label: String,
+    pub duration: Duration,
+}
+
+/// Times passes with timestamp queries and reads the results back a few frames later.
+pub(crate) struct Profiler {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    period: f32,
+    state: Mutex<ProfilerState>,
+}
+
+#[derive(Default)]
+struct ProfilerState {
+    labels: Vec<String>,
+    free_buffers: Vec<wgpu::Buffer>,
+    pending: VecDeque<PendingFrame>,
+    timings: Vec<PassTiming>,
+    overflowed: bool,
+}
+
+impl ProfilerState {
+    /// Reserves a begin/end timestamp pair for a pass named `label`.
+    fn allocate(&mut self, label: &str) -> Option<(u32, u32)> {
+        let index = self.labels.len() as u32 * 2;
+        if index + 2 > MAX_QUERIES {
+            if !self.overflowed {
+                tracing::warn!(
+                    "More than {} passes timed in one frame, ignoring the rest",
+                    MAX_QUERIES / 2
+                );
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        self.labels.push(label.to_string());
+        Some((index, index + 1))
+    }
+}
+
+struct PendingFrame {
+    buffer: wgpu::Buffer,
+    labels: Vec<String>,
+    mapped: Option<mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl Profiler {
+    /// Creates a profiler, or returns `None` if the device lacks `TIMESTAMP_QUERY`.
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            tracing::warn!("Timestamp queries are not supported, GPU profiling is disabled");
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Profiler Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: MAX_QUERIES,
+        });
+
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size: (MAX_QUERIES * wgpu::QUERY_SIZE) as u64,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            period: queue.get_timestamp_period(),
+            state: Mutex::default(),
+        })
+    }
+
+    fn allocate(&self, label: &str) -> Option<(u32, u32)> {
+        self.state.lock().unwrap().allocate(label)
+    }
+
+    pub(crate) fn render_timestamp_writes(
+        &self,
+        label: &str,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        self.allocate(label)
+            .map(|(begin, end)| wgpu::RenderPassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(begin),
+                end_of_pass_write_index: Some(end),
+            })
+    }
+
+    pub(crate) fn compute_timestamp_writes(
+        &self,
+        label: &str,
+    ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        self.allocate(label)
+            .map(|(begin, end)| wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(begin),
+                end_of_pass_write_index: Some(end),
+            })
+    }
+
+    /// Records the resolve and copy of the timestamps written since the last call into
+    /// `encoder`.
+    pub(crate) fn end_frame(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let mut state = self.state.lock().unwrap();
+        let labels = std::mem::take(&mut state.labels);
+        if labels.is_empty() {
+            return;
+        }
+
+        let buffer = match state.free_buffers.pop() {
+            Some(buffer) => buffer,
+            None if state.pending.len() < MAX_PENDING_FRAMES => {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size: (MAX_QUERIES * wgpu::QUERY_SIZE) as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })
+            }
+            None => return,
+        };
+
+        let count = labels.len() as u32 * 2;
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &buffer,
+            0,
+            (count * wgpu::QUERY_SIZE) as u64,
+        );
+
+        state.pending.push_back(PendingFrame {
+            buffer,
+            labels,
+            mapped: None,
+        });
+    }
+
+    /// Starts mapping the frames resolved since the last submission and collects the
+    /// timings of those that have been read back.
+    pub(crate) fn after_submit(&self, device: &wgpu::Device) {
+        let mut state = self.state.lock().unwrap();
+        state.overflowed = false;
+
+        for frame in state
+            .pending
+            .iter_mut()
+            .filter(|frame| frame.mapped.is_none())
+        {
+            let (sender, receiver) = mpsc::channel();
+            frame
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+            frame.mapped = Some(receiver);
+        }
+
+        if let Err(e) = device.poll(wgpu::PollType::Poll) {
+            tracing::error!("Failed to poll device: {}", e);
+        }
+
+        while let Some(frame) = state.pending.front() {
+            let result = match frame.mapped.as_ref().map(mpsc::Receiver::try_recv) {
+                Some(Ok(result)) => result,
+                Some(Err(mpsc::TryRecvError::Empty)) | None => break,
+                Some(Err(mpsc::TryRecvError::Disconnected)) => Err(wgpu::BufferAsyncError),
+            };
+            let Some(frame) = state.pending.pop_front() else {
+                break;
+            };
+
+            match result {
+                Ok(()) => {
+                    state.timings = self.read_timings(&frame);
+                    frame.buffer.unmap();
+                    state.free_buffers.push(frame.buffer);
+                }
+                Err(e) => tracing::error!("Failed to read back GPU timestamps: {}", e),
+            }
+        }
+    }
+
+    fn read_timings(&self, frame: &PendingFrame) -> Vec<PassTiming> {
+        let data = frame.buffer.slice(..).get_mapped_range();
+        let timestamp = |index: usize| {
+            let bytes = &data[index * 8..index * 8 + 8];
+            u64::from_le_bytes(bytes.try_into().unwrap())
+        };
+
+        frame
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let ticks = timestamp(i * 2 + 1).saturating_sub(timestamp(i * 2));
+                PassTiming {
+                    label: label.clone(),
+                    duration: Duration::from_nanos((ticks as f64 * self.period as f64) as u64),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the timings of the most recent frame whose timestamps have been read back.
+    pub(crate) fn timings(&self) -> Vec<PassTiming> {
+        self.state.lock().unwrap().timings.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocates_timestamp_pairs_in_pass_order() {
+        let mut state = ProfilerState::default();
+
+        assert_eq!(state.allocate("Shadow Pass"), Some((0, 1)));
+        assert_eq!(state.allocate("Main Pass"), Some((2, 3)));
+        assert_eq!(state.allocate("Main Pass"), Some((4, 5)));
+        assert_eq!(state.labels, ["Shadow Pass", "Main Pass", "Main Pass"]);
+        assert!(!state.overflowed);
+    }
+
+    #[test]
+    fn ignores_passes_past_the_query_limit() {
+        let mut state = ProfilerState::default();
+        for i in 0..MAX_QUERIES / 2 {
+            assert_eq!(state.allocate("Pass"), Some((i * 2, i * 2 + 1)));
+        }
+
+        assert_eq!(state.allocate("Overflow"), None);
+        assert_eq!(state.allocate("Overflow"), None);
+        assert!(state.overflowed);
+        assert_eq!(state.labels.len() as u32, MAX_QUERIES / 2);
+        assert!(!state.labels.iter().any(|label| label == "Overflow"));
+    }
+}
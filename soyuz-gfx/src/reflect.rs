@@ -0,0 +1,743 @@
+use std::num::NonZeroU64;
+
+/// Entry points, resource bindings and vertex inputs of a WGSL module, as parsed by naga.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    pub entry_points: Vec<EntryPoint>,
+    /// Resource bindings sorted by group and binding.
+    pub bindings: Vec<ResourceBinding>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: wgpu::ShaderStages,
+    /// `@workgroup_size` of compute entry points, `[0, 0, 0]` for other stages.
+    pub workgroup_size: [u32; 3],
+    /// Location inputs of vertex entry points, sorted by location.
+    pub vertex_inputs: Vec<VertexInput>,
+}
+
+/// A `@group(g) @binding(b)` resource.
+#[derive(Debug, Clone)]
+pub struct ResourceBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub ty: wgpu::BindingType,
+    /// Element count of binding arrays.
+    pub count: Option<std::num::NonZeroU32>,
+    /// Stages of the entry points that use the resource.
+    pub visibility: wgpu::ShaderStages,
+}
+
+/// A `@location(n)` input of a vertex entry point.
+#[derive(Debug, Clone)]
+pub struct VertexInput {
+    pub location: u32,
+    pub name: Option<String>,
+    /// The vertex format matching the input's type exactly, if there is one.
+    pub format: Option<wgpu::VertexFormat>,
+}
+
+impl EntryPoint {
+    /// Describes the vertex inputs with no attribute in `attributes`, or with an attribute
+    /// the shader reads as another type.
+    pub(crate) fn vertex_input_mismatches<'v>(
+        &self,
+        attributes: impl IntoIterator<Item = &'v wgpu::VertexAttribute> + Clone,
+    ) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        for input in &self.vertex_inputs {
+            let attribute = attributes
+                .clone()
+                .into_iter()
+                .find(|attribute| attribute.shader_location == input.location);
+            match (attribute, input.format) {
+                (None, _) => mismatches.push(format!(
+                    "vertex input {} at location {} of `{}` has no vertex attribute",
+                    input.name.as_deref().unwrap_or("<unnamed>"),
+                    input.location,
+                    self.name
+                )),
+                (Some(attribute), Some(format))
+                    if vertex_kind(attribute.format) != vertex_kind(format) =>
+                {
+                    mismatches.push(format!(
+                        "vertex attribute at location {} is {:?} but `{}` reads it as {:?}",
+                        input.location, attribute.format, self.name, format
+                    ))
+                }
+                _ => {}
+            }
+        }
+        mismatches
+    }
+}
+
+impl ShaderReflection {
+    /// Parses `source` and collects its reflection data.
+    pub fn from_wgsl(source: &str) -> Result<Self, naga::front::wgsl::ParseError> {
+        let module = naga::front::wgsl::parse_str(source)?;
+        Ok(Self::from_module(&module))
+    }
+
+    fn from_module(module: &naga::Module) -> Self {
+        // Validation tells which globals each entry point uses. Without it, every
+        // resource is assumed visible to every stage in the module.
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(module)
+        .map_err(|e| tracing::debug!("Shader reflection without validation: {}", e))
+        .ok();
+
+        let entry_points = module
+            .entry_points
+            .iter()
+            .map(|entry_point| EntryPoint {
+                name: entry_point.name.clone(),
+                stage: map_stage(entry_point.stage),
+                workgroup_size: entry_point.workgroup_size,
+                vertex_inputs: if entry_point.stage == naga::ShaderStage::Vertex {
+                    vertex_inputs(module, &entry_point.function)
+                } else {
+                    Vec::new()
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let all_stages = entry_points
+            .iter()
+            .fold(wgpu::ShaderStages::NONE, |stages, entry_point| {
+                stages | entry_point.stage
+            });
+
+        let mut bindings = module
+            .global_variables
+            .iter()
+            .filter_map(|(handle, global)| {
+                let binding = global.binding.as_ref()?;
+                let (ty, count) = binding_type(module, global)?;
+                let visibility = match info.as_ref() {
+                    Some(info) => module
+                        .entry_points
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| !info.get_entry_point(i)[handle].is_empty())
+                        .fold(wgpu::ShaderStages::NONE, |stages, (_, entry_point)| {
+                            stages | map_stage(entry_point.stage)
+                        }),
+                    None => all_stages,
+                };
+
+                Some(ResourceBinding {
+                    group: binding.group,
+                    binding: binding.binding,
+                    name: global.name.clone(),
+                    ty,
+                    count,
+                    visibility,
+                })
+            })
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+        Self {
+            entry_points,
+            bindings,
+        }
+    }
+
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        self.entry_points
+            .iter()
+            .find(|entry_point| entry_point.name == name)
+    }
+
+    /// Finds the entry point of `stage` named `name`, or the only one of that stage when
+    /// no name is given, as wgpu does. Errors describe the mismatch.
+    pub(crate) fn find_entry_point(
+        &self,
+        stage: wgpu::ShaderStages,
+        name: Option<&str>,
+    ) -> Result<&EntryPoint, String> {
+        let mut candidates = self
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage == stage);
+        let stage = match stage {
+            wgpu::ShaderStages::VERTEX => "vertex",
+            wgpu::ShaderStages::FRAGMENT => "fragment",
+            _ => "compute",
+        };
+
+        match name {
+            Some(name) => candidates
+                .find(|entry_point| entry_point.name == name)
+                .ok_or_else(|| format!("shader has no {} entry point `{}`", stage, name)),
+            None => match (candidates.next(), candidates.next()) {
+                (Some(entry_point), None) => Ok(entry_point),
+                (None, _) => Err(format!("shader has no {} entry point", stage)),
+                (Some(_), Some(_)) => Err(format!(
+                    "shader has several {} entry points and none was named",
+                    stage
+                )),
+            },
+        }
+    }
+
+    /// Returns the number of bind groups, one past the highest group index used.
+    pub fn group_count(&self) -> u32 {
+        self.bindings
+            .iter()
+            .map(|binding| binding.group + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the layout entries of bind group `group`.
+    ///
+    /// Resources unused by every entry point are made visible to all stages of the
+    /// module so that the layout stays valid.
+    pub fn bind_group_layout_entries(&self, group: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        let all_stages = self
+            .entry_points
+            .iter()
+            .fold(wgpu::ShaderStages::NONE, |stages, entry_point| {
+                stages | entry_point.stage
+            });
+
+        self.bindings
+            .iter()
+            .filter(|binding| binding.group == group)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding: binding.binding,
+                visibility: if binding.visibility.is_empty() {
+                    all_stages
+                } else {
+                    binding.visibility
+                },
+                ty: binding.ty,
+                count: binding.count,
+            })
+            .collect()
+    }
+}
+
+fn map_stage(stage: naga::ShaderStage) -> wgpu::ShaderStages {
+    match stage {
+        naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+        naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+        naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+        naga::ShaderStage::Task => wgpu::ShaderStages::TASK,
+        naga::ShaderStage::Mesh => wgpu::ShaderStages::MESH,
+    }
+}
+
+/// Maps a global variable to a binding type and binding array size. Returns `None` for
+/// resources wgpu binds some other way, such as acceleration structures.
+fn binding_type(
+    module: &naga::Module,
+    global: &naga::GlobalVariable,
+) -> Option<(wgpu::BindingType, Option<std::num::NonZeroU32>)> {
+    let (ty, count) = match module.types[global.ty].inner {
+        naga::TypeInner::BindingArray {
+            base,
+            size: naga::ArraySize::Constant(size),
+        } => (base, Some(size)),
+        _ => (global.ty, None),
+    };
+
+    let buffer = |buffer_type: wgpu::BufferBindingType| wgpu::BindingType::Buffer {
+        ty: buffer_type,
+        has_dynamic_offset: false,
+        min_binding_size: NonZeroU64::new(module.types[ty].inner.size(module.to_ctx()) as u64),
+    };
+
+    let binding_type = match global.space {
+        naga::AddressSpace::Uniform => buffer(wgpu::BufferBindingType::Uniform),
+        naga::AddressSpace::Storage { access } => buffer(wgpu::BufferBindingType::Storage {
+            read_only: !access.contains(naga::StorageAccess::STORE),
+        }),
+        naga::AddressSpace::Handle => match module.types[ty].inner {
+            naga::TypeInner::Sampler { comparison } => wgpu::BindingType::Sampler(if comparison {
+                wgpu::SamplerBindingType::Comparison
+            } else {
+                wgpu::SamplerBindingType::Filtering
+            }),
+            naga::TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            } => {
+                let view_dimension = match (dim, arrayed) {
+                    (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+                    (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                    (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                    (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+                    (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                    (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+                };
+                match class {
+                    // Whether a float texture is filterable cannot be told from the
+                    // shader, so it is assumed to be.
+                    naga::ImageClass::Sampled { kind, multi } => wgpu::BindingType::Texture {
+                        sample_type: match kind {
+                            naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                            naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                            _ => wgpu::TextureSampleType::Float { filterable: !multi },
+                        },
+                        view_dimension,
+                        multisampled: multi,
+                    },
+                    naga::ImageClass::Depth { multi } => wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension,
+                        multisampled: multi,
+                    },
+                    naga::ImageClass::Storage { format, access } => {
+                        wgpu::BindingType::StorageTexture {
+                            access: match (
+                                access.contains(naga::StorageAccess::LOAD),
+                                access.contains(naga::StorageAccess::STORE),
+                            ) {
+                                (true, true) => wgpu::StorageTextureAccess::ReadWrite,
+                                (true, false) => wgpu::StorageTextureAccess::ReadOnly,
+                                _ => wgpu::StorageTextureAccess::WriteOnly,
+                            },
+                            format: map_storage_format(format),
+                            view_dimension,
+                        }
+                    }
+                    naga::ImageClass::External => wgpu::BindingType::ExternalTexture,
+                }
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some((binding_type, count))
+}
+
+fn map_storage_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+    use naga::StorageFormat as Sf;
+    use wgpu::TextureFormat as Tf;
+
+    match format {
+        Sf::R8Unorm => Tf::R8Unorm,
+        Sf::R8Snorm => Tf::R8Snorm,
+        Sf::R8Uint => Tf::R8Uint,
+        Sf::R8Sint => Tf::R8Sint,
+        Sf::R16Uint => Tf::R16Uint,
+        Sf::R16Sint => Tf::R16Sint,
+        Sf::R16Float => Tf::R16Float,
+        Sf::Rg8Unorm => Tf::Rg8Unorm,
+        Sf::Rg8Snorm => Tf::Rg8Snorm,
+        Sf::Rg8Uint => Tf::Rg8Uint,
+        Sf::Rg8Sint => Tf::Rg8Sint,
+        Sf::R32Uint => Tf::R32Uint,
+        Sf::R32Sint => Tf::R32Sint,
+        Sf::R32Float => Tf::R32Float,
+        Sf::Rg16Uint => Tf::Rg16Uint,
+        Sf::Rg16Sint => Tf::Rg16Sint,
+        Sf::Rg16Float => Tf::Rg16Float,
+        Sf::Rgba8Unorm => Tf::Rgba8Unorm,
+        Sf::Rgba8Snorm => Tf::Rgba8Snorm,
+        Sf::Rgba8Uint => Tf::Rgba8Uint,
+        Sf::Rgba8Sint => Tf::Rgba8Sint,
+        Sf::Bgra8Unorm => Tf::Bgra8Unorm,
+        Sf::Rgb10a2Uint => Tf::Rgb10a2Uint,
+        Sf::Rgb10a2Unorm => Tf::Rgb10a2Unorm,
+        Sf::Rg11b10Ufloat => Tf::Rg11b10Ufloat,
+        Sf::R64Uint => Tf::R64Uint,
+        Sf::Rg32Uint => Tf::Rg32Uint,
+        Sf::Rg32Sint => Tf::Rg32Sint,
+        Sf::Rg32Float => Tf::Rg32Float,
+        Sf::Rgba16Uint => Tf::Rgba16Uint,
+        Sf::Rgba16Sint => Tf::Rgba16Sint,
+        Sf::Rgba16Float => Tf::Rgba16Float,
+        Sf::Rgba32Uint => Tf::Rgba32Uint,
+        Sf::Rgba32Sint => Tf::Rgba32Sint,
+        Sf::Rgba32Float => Tf::Rgba32Float,
+        Sf::R16Unorm => Tf::R16Unorm,
+        Sf::R16Snorm => Tf::R16Snorm,
+        Sf::Rg16Unorm => Tf::Rg16Unorm,
+        Sf::Rg16Snorm => Tf::Rg16Snorm,
+        Sf::Rgba16Unorm => Tf::Rgba16Unorm,
+        Sf::Rgba16Snorm => Tf::Rgba16Snorm,
+    }
+}
+
+/// Collects the location inputs of an entry point, looking inside struct arguments.
+fn vertex_inputs(module: &naga::Module, function: &naga::Function) -> Vec<VertexInput> {
+    let mut inputs = Vec::new();
+    let mut add = |name: &Option<String>, ty: naga::Handle<naga::Type>, binding: &naga::Binding| {
+        if let naga::Binding::Location { location, .. } = *binding {
+            inputs.push(VertexInput {
+                location,
+                name: name.clone(),
+                format: vertex_format(&module.types[ty].inner),
+            });
+        }
+    };
+
+    for argument in &function.arguments {
+        match (&argument.binding, &module.types[argument.ty].inner) {
+            (Some(binding), _) => add(&argument.name, argument.ty, binding),
+            (None, naga::TypeInner::Struct { members, .. }) => {
+                for member in members {
+                    if let Some(binding) = &member.binding {
+                        add(&member.name, member.ty, binding);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    inputs.sort_by_key(|input| input.location);
+    inputs
+}
+
+fn vertex_format(inner: &naga::TypeInner) -> Option<wgpu::VertexFormat> {
+    use wgpu::VertexFormat as Vf;
+
+    let (scalar, components) = match *inner {
+        naga::TypeInner::Scalar(scalar) => (scalar, 1),
+        naga::TypeInner::Vector { size, scalar } => (scalar, size as u8),
+        _ => return None,
+    };
+
+    Some(match (scalar.kind, scalar.width, components) {
+        (naga::ScalarKind::Float, 4, 1) => Vf::Float32,
+        (naga::ScalarKind::Float, 4, 2) => Vf::Float32x2,
+        (naga::ScalarKind::Float, 4, 3) => Vf::Float32x3,
+        (naga::ScalarKind::Float, 4, 4) => Vf::Float32x4,
+        (naga::ScalarKind::Float, 2, 1) => Vf::Float16,
+        (naga::ScalarKind::Float, 2, 2) => Vf::Float16x2,
+        (naga::ScalarKind::Float, 2, 4) => Vf::Float16x4,
+        (naga::ScalarKind::Float, 8, 1) => Vf::Float64,
+        (naga::ScalarKind::Float, 8, 2) => Vf::Float64x2,
+        (naga::ScalarKind::Float, 8, 3) => Vf::Float64x3,
+        (naga::ScalarKind::Float, 8, 4) => Vf::Float64x4,
+        (naga::ScalarKind::Uint, 4, 1) => Vf::Uint32,
+        (naga::ScalarKind::Uint, 4, 2) => Vf::Uint32x2,
+        (naga::ScalarKind::Uint, 4, 3) => Vf::Uint32x3,
+        (naga::ScalarKind::Uint, 4, 4) => Vf::Uint32x4,
+        (naga::ScalarKind::Sint, 4, 1) => Vf::Sint32,
+        (naga::ScalarKind::Sint, 4, 2) => Vf::Sint32x2,
+        (naga::ScalarKind::Sint, 4, 3) => Vf::Sint32x3,
+        (naga::ScalarKind::Sint, 4, 4) => Vf::Sint32x4,
+        _ => return None,
+    })
+}
+
+/// The type of value a vertex format is read as in a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VertexKind {
+    Float,
+    Uint,
+    Sint,
+}
+
+fn vertex_kind(format: wgpu::VertexFormat) -> VertexKind {
+    use wgpu::VertexFormat as Vf;
+
+    match format {
+        Vf::Uint8
+        | Vf::Uint8x2
+        | Vf::Uint8x4
+        | Vf::Uint16
+        | Vf::Uint16x2
+        | Vf::Uint16x4
+        | Vf::Uint32
+        | Vf::Uint32x2
+        | Vf::Uint32x3
+        | Vf::Uint32x4 => VertexKind::Uint,
+        Vf::Sint8
+        | Vf::Sint8x2
+        | Vf::Sint8x4
+        | Vf::Sint16
+        | Vf::Sint16x2
+        | Vf::Sint16x4
+        | Vf::Sint32
+        | Vf::Sint32x2
+        | Vf::Sint32x3
+        | Vf::Sint32x4 => VertexKind::Sint,
+        _ => VertexKind::Float,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = r#"
+struct Camera {
+    view_proj: mat4x4<f32>,
+    position: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(1) uv: vec2<f32>,
+    @location(0) position: vec3<f32>,
+    @location(2) material: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var<storage, read> weights: array<f32>;
+@group(1) @binding(0) var color_texture: texture_2d<f32>;
+@group(1) @binding(1) var color_sampler: sampler;
+@group(1) @binding(2) var shadow_map: texture_depth_2d_array;
+@group(1) @binding(3) var shadow_sampler: sampler_comparison;
+@group(2) @binding(0) var unused_texture: texture_2d<u32>;
+
+@vertex
+fn vs_main(in: VertexInput, @location(3) offset: vec4<i32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = camera.view_proj * vec4<f32>(in.position + vec3<f32>(offset.xyz), 1.0);
+    out.uv = in.uv * weights[in.material];
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let shadow = textureSampleCompare(shadow_map, shadow_sampler, in.uv, 0, 0.5);
+    return textureSample(color_texture, color_sampler, in.uv) * shadow * camera.position;
+}
+
+@fragment
+fn fs_unlit() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+"#;
+
+    const COMPUTE: &str = r#"
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+@group(0) @binding(1) var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 4)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    data[id.x] = id.y;
+    textureStore(output, id.xy, vec4<f32>(0.0));
+}
+"#;
+
+    fn binding(reflection: &ShaderReflection, group: u32, binding: u32) -> &ResourceBinding {
+        reflection
+            .bindings
+            .iter()
+            .find(|b| b.group == group && b.binding == binding)
+            .unwrap()
+    }
+
+    #[test]
+    fn reflects_binding_types() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+
+        assert_eq!(reflection.bindings.len(), 7);
+        assert_eq!(reflection.group_count(), 3);
+        assert_eq!(binding(&reflection, 0, 0).name.as_deref(), Some("camera"));
+        assert_eq!(
+            binding(&reflection, 0, 0).ty,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(80),
+            }
+        );
+        assert_eq!(
+            binding(&reflection, 0, 1).ty,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(4),
+            }
+        );
+        assert_eq!(
+            binding(&reflection, 1, 0).ty,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            binding(&reflection, 1, 1).ty,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+        );
+        assert_eq!(
+            binding(&reflection, 1, 2).ty,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            binding(&reflection, 1, 3).ty,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison)
+        );
+        assert_eq!(
+            binding(&reflection, 2, 0).ty,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Uint,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            }
+        );
+
+        let compute = ShaderReflection::from_wgsl(COMPUTE).unwrap();
+        assert_eq!(
+            binding(&compute, 0, 0).ty,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(4),
+            }
+        );
+        assert_eq!(
+            binding(&compute, 0, 1).ty,
+            wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            }
+        );
+    }
+
+    #[test]
+    fn folds_visibility_of_entry_points() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+
+        assert_eq!(
+            binding(&reflection, 0, 0).visibility,
+            wgpu::ShaderStages::VERTEX_FRAGMENT
+        );
+        assert_eq!(
+            binding(&reflection, 0, 1).visibility,
+            wgpu::ShaderStages::VERTEX
+        );
+        assert_eq!(
+            binding(&reflection, 1, 0).visibility,
+            wgpu::ShaderStages::FRAGMENT
+        );
+        assert_eq!(
+            binding(&reflection, 2, 0).visibility,
+            wgpu::ShaderStages::NONE
+        );
+
+        // Unused resources are made visible to every stage of the module.
+        let entries = reflection.bind_group_layout_entries(2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].visibility, wgpu::ShaderStages::VERTEX_FRAGMENT);
+
+        let compute = ShaderReflection::from_wgsl(COMPUTE).unwrap();
+        assert_eq!(
+            compute.bind_group_layout_entries(0)[1].visibility,
+            wgpu::ShaderStages::COMPUTE
+        );
+    }
+
+    #[test]
+    fn reflects_entry_points_and_vertex_inputs() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+
+        let vs_main = reflection.entry_point("vs_main").unwrap();
+        assert_eq!(vs_main.stage, wgpu::ShaderStages::VERTEX);
+        let inputs = vs_main
+            .vertex_inputs
+            .iter()
+            .map(|input| (input.location, input.name.as_deref(), input.format))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            [
+                (0, Some("position"), Some(wgpu::VertexFormat::Float32x3)),
+                (1, Some("uv"), Some(wgpu::VertexFormat::Float32x2)),
+                (2, Some("material"), Some(wgpu::VertexFormat::Uint32)),
+                (3, Some("offset"), Some(wgpu::VertexFormat::Sint32x4)),
+            ]
+        );
+
+        let fs_main = reflection.entry_point("fs_main").unwrap();
+        assert_eq!(fs_main.stage, wgpu::ShaderStages::FRAGMENT);
+        assert!(fs_main.vertex_inputs.is_empty());
+
+        let compute = ShaderReflection::from_wgsl(COMPUTE).unwrap();
+        assert_eq!(
+            compute.entry_point("cs_main").unwrap().workgroup_size,
+            [8, 4, 1]
+        );
+    }
+
+    #[test]
+    fn reports_entry_point_mismatches() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+
+        assert_eq!(
+            reflection
+                .find_entry_point(wgpu::ShaderStages::VERTEX, None)
+                .unwrap()
+                .name,
+            "vs_main"
+        );
+        assert_eq!(
+            reflection
+                .find_entry_point(wgpu::ShaderStages::FRAGMENT, Some("fs_unlit"))
+                .unwrap()
+                .name,
+            "fs_unlit"
+        );
+        assert_eq!(
+            reflection
+                .find_entry_point(wgpu::ShaderStages::FRAGMENT, None)
+                .unwrap_err(),
+            "shader has several fragment entry points and none was named"
+        );
+        assert_eq!(
+            reflection
+                .find_entry_point(wgpu::ShaderStages::VERTEX, Some("fs_main"))
+                .unwrap_err(),
+            "shader has no vertex entry point `fs_main`"
+        );
+        assert_eq!(
+            reflection
+                .find_entry_point(wgpu::ShaderStages::COMPUTE, None)
+                .unwrap_err(),
+            "shader has no compute entry point"
+        );
+    }
+
+    #[test]
+    fn reports_vertex_input_mismatches() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+        let vs_main = reflection.entry_point("vs_main").unwrap();
+
+        let attributes = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Unorm16x2,
+            2 => Uint32,
+            3 => Sint32x4,
+        ];
+        assert!(vs_main.vertex_input_mismatches(&attributes).is_empty());
+
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3, 2 => Float32];
+        assert_eq!(
+            vs_main.vertex_input_mismatches(&attributes),
+            [
+                "vertex input uv at location 1 of `vs_main` has no vertex attribute",
+                "vertex attribute at location 2 is Float32 but `vs_main` reads it as Uint32",
+                "vertex input offset at location 3 of `vs_main` has no vertex attribute",
+            ]
+        );
+    }
+}
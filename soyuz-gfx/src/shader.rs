@@ -1,17 +1,34 @@
+use crate::Result;
+use crate::error::capture_errors;
+use crate::reflect::ShaderReflection;
+
 pub struct Shader {
     module: wgpu::ShaderModule,
+    reflection: Option<ShaderReflection>,
 }
 
 impl Shader {
     pub fn from_wgsl(device: &wgpu::Device, source: &str) -> Self {
+        // Parse errors are left for wgpu to report when it compiles the module.
+        let reflection = ShaderReflection::from_wgsl(source)
+            .map_err(|e| tracing::debug!("Failed to reflect shader: {}", e))
+            .ok();
+
         Self {
             module: device.create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: None,
                 source: wgpu::ShaderSource::Wgsl(source.into()),
             }),
+            reflection,
         }
     }
 
+    /// Compiles WGSL source, returning parse and validation errors instead of reporting
+    /// them to the device's uncaptured error handler.
+    pub fn try_from_wgsl(device: &wgpu::Device, source: &str) -> Result<Self> {
+        capture_errors(device, || Self::from_wgsl(device, source))
+    }
+
     pub fn from_wgsl_file(device: &wgpu::Device, path: &str) -> Result<Self, std::io::Error> {
         let source = std::fs::read_to_string(path)?;
         Ok(Self::from_wgsl(device, &source))
@@ -20,4 +37,36 @@ impl Shader {
     pub fn module(&self) -> &wgpu::ShaderModule {
         &self.module
     }
+
+    /// Returns the entry points, bindings and vertex inputs of the shader, or `None` if
+    /// its source failed to parse.
+    pub fn reflection(&self) -> Option<&ShaderReflection> {
+        self.reflection.as_ref()
+    }
+
+    /// Returns the `@workgroup_size` of the compute entry point `entry`, or `None` if there
+    /// is no such entry point or its size depends on pipeline-overridable constants.
+    pub fn workgroup_size(&self, entry: &str) -> Option<[u32; 3]> {
+        let entry_point = self.reflection.as_ref()?.entry_point(entry)?;
+        (entry_point.stage == wgpu::ShaderStages::COMPUTE
+            && entry_point.workgroup_size.iter().all(|&n| n > 0))
+        .then_some(entry_point.workgroup_size)
+    }
+
+    /// Creates a bind group layout for each group from 0 to the highest one the shader
+    /// declares, empty for groups it skips.
+    pub fn bind_group_layouts(&self, device: &wgpu::Device) -> Vec<wgpu::BindGroupLayout> {
+        let Some(reflection) = self.reflection.as_ref() else {
+            return Vec::new();
+        };
+
+        (0..reflection.group_count())
+            .map(|group| {
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Reflected Bind Group Layout"),
+                    entries: &reflection.bind_group_layout_entries(group),
+                })
+            })
+            .collect()
+    }
 }
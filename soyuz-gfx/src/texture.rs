@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use crate::{Error, MipmapGenerator, Result};
+
+/// A 2D texture with a view of all its mip levels and a sampler.
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+}
+
+/// Creates [`Texture`]s from image files or RGBA pixels.
+///
+/// Textures are sRGB `Rgba8UnormSrgb` by default, sampled with linear filtering and
+/// clamped to the edge.
+pub struct TextureBuilder<'a> {
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    srgb: bool,
+    mipmaps: bool,
+    address_mode: wgpu::AddressMode,
+    filter: wgpu::FilterMode,
+    usage: wgpu::TextureUsages,
+    label: Option<&'a str>,
+    mipmap_generator: Option<&'a MipmapGenerator>,
+}
+
+impl<'a> TextureBuilder<'a> {
+    pub fn new(device: &'a wgpu::Device, queue: &'a wgpu::Queue) -> Self {
+        Self {
+            device,
+            queue,
+            srgb: true,
+            mipmaps: false,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            filter: wgpu::FilterMode::Linear,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: None,
+            mipmap_generator: None,
+        }
+    }
+
+    /// Stores the pixels as sRGB, decoded to linear when sampled. Disable for data such as
+    /// normal maps that is already linear.
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Creates the full mip chain down to 1x1 and fills it from the base level, on the GPU
+    /// if a [`MipmapGenerator`] is set and on the CPU otherwise.
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn address_mode(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_mode = mode;
+        self
+    }
+
+    pub fn filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Adds usages to the default `TEXTURE_BINDING | COPY_DST`.
+    pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage |= usage;
+        self
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Generates mipmaps with `generator`, adding `RENDER_ATTACHMENT` usage to mipmapped
+    /// textures.
+    pub fn mipmap_generator(mut self, generator: &'a MipmapGenerator) -> Self {
+        self.mipmap_generator = Some(generator);
+        self
+    }
+
+    /// Decodes a PNG or JPEG image from memory.
+    pub fn build_from_bytes(self, bytes: &[u8]) -> Result<Texture> {
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        self.build_from_rgba(image.width(), image.height(), image.as_raw())
+    }
+
+    /// Reads and decodes a PNG or JPEG image file.
+    pub fn build_from_path(self, path: impl AsRef<Path>) -> Result<Texture> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.build_from_bytes(&bytes)
+    }
+
+    /// Creates a texture from tightly packed 8-bit RGBA pixels.
+    pub fn build_from_rgba(self, width: u32, height: u32, pixels: &[u8]) -> Result<Texture> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(Error::InvalidTexture(format!(
+                "{}x{} RGBA pixels take {} bytes, got {}",
+                width,
+                height,
+                expected,
+                pixels.len()
+            )));
+        }
+
+        let max = self.device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(Error::InvalidTexture(format!(
+                "size {}x{} is outside 1x1 to {}x{}",
+                width, height, max, max
+            )));
+        }
+
+        let format = if self.srgb {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let mip_level_count = if self.mipmaps {
+            size.max_mips(wgpu::TextureDimension::D2)
+        } else {
+            1
+        };
+
+        let generator = self.mipmap_generator.filter(|_| mip_level_count > 1);
+        let usage = if generator.is_some() {
+            self.usage | wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            self.usage
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: self.label,
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+
+        let level = image::RgbaImage::from_raw(width, height, pixels.to_vec())
+            .expect("pixel buffer size was checked above");
+        write_level(self.queue, &texture, 0, &level);
+
+        if let Some(generator) = generator {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mipmap Encoder"),
+                });
+            generator.generate(self.device, &mut encoder, &texture)?;
+            self.queue.submit(Some(encoder.finish()));
+        } else if mip_level_count > 1 {
+            // sRGB levels are filtered in linear space, or they come out darker.
+            let mut linear = image::Rgba32FImage::from_fn(width, height, |x, y| {
+                let pixel = level.get_pixel(x, y).0;
+                image::Rgba(std::array::from_fn(|c| {
+                    let value = pixel[c] as f32 / 255.0;
+                    if self.srgb && c < 3 {
+                        srgb_to_linear(value)
+                    } else {
+                        value
+                    }
+                }))
+            });
+            for mip_level in 1..mip_level_count {
+                linear = image::imageops::resize(
+                    &linear,
+                    (linear.width() / 2).max(1),
+                    (linear.height() / 2).max(1),
+                    image::imageops::FilterType::Triangle,
+                );
+                let level = image::RgbaImage::from_fn(linear.width(), linear.height(), |x, y| {
+                    let pixel = linear.get_pixel(x, y).0;
+                    image::Rgba(std::array::from_fn(|c| {
+                        let value = if self.srgb && c < 3 {
+                            linear_to_srgb(pixel[c])
+                        } else {
+                            pixel[c]
+                        };
+                        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                    }))
+                });
+                write_level(self.queue, &texture, mip_level, &level);
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: self.label,
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: self.filter,
+            ..Default::default()
+        });
+
+        Ok(Texture {
+            texture,
+            view,
+            sampler,
+        })
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn write_level(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    mip_level: u32,
+    image: &image::RgbaImage,
+) {
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        image.as_raw(),
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(image.width() * 4),
+            rows_per_image: Some(image.height()),
+        },
+        wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        },
+    );
+}
@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use bytemuck::Pod;
+
+/// Uniform buffers are padded to a multiple of this size, the alignment of WGSL structs
+/// containing vectors in the uniform address space.
+const UNIFORM_ALIGNMENT: usize = 16;
+
+/// A uniform buffer holding a `T`, with its own bind group layout and bind group.
+///
+/// The bind group contains the buffer alone at the binding given on creation. Add the
+/// uniform to a pipeline with [`crate::RenderPipelineBuilder::uniform`] and bind it with
+/// [`Uniform::bind`].
+#[derive(Debug, Clone)]
+pub struct Uniform<T: Pod> {
+    buffer: wgpu::Buffer,
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Uniform<T> {
+    pub fn new(
+        device: &wgpu::Device,
+        value: &T,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size: size_of::<T>().max(1).next_multiple_of(UNIFORM_ALIGNMENT) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+        buffer.slice(..).get_mapped_range_mut()[..size_of::<T>()]
+            .copy_from_slice(bytemuck::bytes_of(value));
+        buffer.unmap();
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Uniform Bind Group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            buffer,
+            layout,
+            bind_group,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Writes `value` to the buffer through the queue.
+    pub fn set(&self, queue: &wgpu::Queue, value: &T) {
+        let bytes = bytemuck::bytes_of(value);
+        if bytes
+            .len()
+            .is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize)
+        {
+            queue.write_buffer(&self.buffer, 0, bytes);
+        } else {
+            let mut padded = bytes.to_vec();
+            padded.resize(
+                bytes
+                    .len()
+                    .next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize),
+                0,
+            );
+            queue.write_buffer(&self.buffer, 0, &padded);
+        }
+    }
+
+    /// Binds the uniform's bind group at `group`.
+    pub fn bind(&self, render_pass: &mut wgpu::RenderPass<'_>, group: u32) {
+        render_pass.set_bind_group(group, &self.bind_group, &[]);
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
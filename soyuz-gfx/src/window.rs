@@ -0,0 +1,11 @@
+use winit::window::{WindowAttributes, WindowId};
+
+/// A window operation requested through a [`Context`](crate::Context), carried out by the
+/// event loop owner (e.g. `soyuz-app`) once control returns to it.
+#[derive(Debug)]
+pub enum WindowRequest {
+    /// Open a new window sharing the device and queue of the requesting context.
+    Open(Box<WindowAttributes>),
+    /// Close the window with this id.
+    Close(WindowId),
+}